@@ -0,0 +1,127 @@
+//! `ask` round-trips per second, run with `cargo bench`.
+//!
+//! Not a statistically rigorous benchmark, just enough to compare changes to the reply path.
+//! The `local` ones run caller and actor on one thread, so cross-thread wake-ups (which dominate
+//! the others) don't hide the cost of the reply itself.
+
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use mailboxxy::*;
+
+
+enum Msg {
+    Get(ReplyChannel<u64>),
+}
+
+async fn echo(ctx: MailboxContext<Msg>) {
+    let mut n = 0;
+    while let Some(Msg::Get(rc)) = ctx.dequeue().await {
+        n += 1;
+        let _ = rc.reply(n);
+    }
+}
+
+/// The reply path `ReplyChannel` replaced, for comparison.
+enum ChannelMsg {
+    Get(async_channel::Sender<u64>),
+}
+
+async fn channel_echo(ctx: MailboxContext<ChannelMsg>) {
+    let mut n = 0;
+    while let Some(ChannelMsg::Get(s)) = ctx.dequeue().await {
+        n += 1;
+        let _ = s.try_send(n);
+    }
+}
+
+struct SmolSpawner;
+
+impl Spawner for SmolSpawner {
+    fn spawn(&self, fut: BoxFuture) {
+        smol::spawn(fut).detach();
+    }
+}
+
+const ASKS: u64 = 200_000;
+
+fn report(name: &str, asks: u64, elapsed: Duration) {
+    let per_ask = elapsed.as_nanos() as f64 / asks as f64;
+    let per_sec = asks as f64 / elapsed.as_secs_f64();
+    println!("{:<24} {:>8.0} ns/ask {:>12.0} asks/s", name, per_ask, per_sec);
+}
+
+/// One caller, waiting for every reply before asking again.
+fn sequential() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, echo, SmolSpawner);
+        let start = Instant::now();
+        for _ in 0..ASKS {
+            mb.ask(Msg::Get).await.unwrap();
+        }
+        report("sequential", ASKS, start.elapsed());
+    });
+}
+
+/// Many asks in flight at the same time.
+fn concurrent(callers: u64) {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, echo, SmolSpawner);
+        let start = Instant::now();
+        let tasks: Vec<_> = (0..callers).map(|_| {
+            let address = mb.address();
+            smol::spawn(async move {
+                for _ in 0..ASKS / callers {
+                    address.ask(Msg::Get).await.unwrap();
+                }
+            })
+        }).collect();
+        for task in tasks {
+            task.await;
+        }
+        report(&format!("concurrent ({} callers)", callers), ASKS / callers * callers, start.elapsed());
+    });
+}
+
+struct LocalSpawner(Rc<smol::LocalExecutor<'static>>);
+
+impl Spawner for LocalSpawner {
+    fn spawn(&self, fut: BoxFuture) {
+        self.0.spawn(fut).detach();
+    }
+}
+
+/// `sequential` with caller and actor on the same thread.
+fn local() {
+    let ex = Rc::new(smol::LocalExecutor::new());
+    smol::block_on(ex.run(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, echo, LocalSpawner(ex.clone()));
+        let start = Instant::now();
+        for _ in 0..ASKS {
+            mb.ask(Msg::Get).await.unwrap();
+        }
+        report("local", ASKS, start.elapsed());
+    }));
+}
+
+/// `local`, but replying through an `async_channel::bounded(1)` instead of a `ReplyChannel`.
+fn local_channel() {
+    let ex = Rc::new(smol::LocalExecutor::new());
+    smol::block_on(ex.run(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, channel_echo, LocalSpawner(ex.clone()));
+        let start = Instant::now();
+        for _ in 0..ASKS {
+            let (s, r) = async_channel::bounded(1);
+            mb.post(ChannelMsg::Get(s)).unwrap();
+            r.recv().await.unwrap();
+        }
+        report("local, bounded(1) reply", ASKS, start.elapsed());
+    }));
+}
+
+fn main() {
+    sequential();
+    concurrent(16);
+    local();
+    local_channel();
+}
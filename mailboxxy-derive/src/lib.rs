@@ -0,0 +1,180 @@
+#![allow(clippy::needless_return)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericArgument, Ident, PathArguments, Type};
+
+
+/// Generates an extension trait `<Enum>Ext` for `mailboxxy::Address<Enum>` (and, through deref,
+/// `MailBox<Enum>`) with one method per variant, named after the variant in snake_case.
+///
+/// Variants with a `ReplyChannel<T>` field become `async` methods built on `ask`, returning
+/// `Result<T, MailboxError>`; all other fields become parameters. Variants without one
+/// become methods built on `post`.
+///
+/// Variants whose method would be hidden by one of `Address`'s (or `MailBox`'s) own methods,
+/// like `Stop` or `Ping`, are rejected:
+///
+/// ```compile_fail
+/// use mailboxxy::*;
+///
+/// #[derive(Mailbox)]
+/// enum Msg {
+///     Stop,
+/// }
+/// ```
+#[proc_macro_derive(Mailbox)]
+pub fn derive_mailbox(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    return match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    };
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, "Mailbox can only be derived for enums"));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "Mailbox can't be derived for generic enums"));
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let trait_name = format_ident!("{}Ext", name);
+
+    let mut signatures = Vec::new();
+    let mut bodies = Vec::new();
+
+    for variant in &data.variants {
+        let variant_name = &variant.ident;
+        let method = method_name(variant_name);
+        if RESERVED.contains(&method.unraw().to_string().as_str()) {
+            let msg = format!(
+                "variant `{}` would generate `{}`, which is hidden by the method of `Address` with the same name; rename the variant",
+                variant_name, method
+            );
+            return Err(syn::Error::new_spanned(variant_name, msg));
+        }
+
+        // (parameter name, type) for every field but the reply channel
+        let mut params = Vec::new();
+        let mut reply = None;
+        let mut values = Vec::new();
+
+        for (i, field) in variant.fields.iter().enumerate() {
+            let ident = field.ident.clone().unwrap_or_else(|| format_ident!("arg{}", i));
+            if let Some(t) = reply_type(&field.ty) {
+                if reply.is_some() {
+                    return Err(syn::Error::new_spanned(field, "only one ReplyChannel per variant is supported"));
+                }
+                reply = Some(t);
+                values.push(quote! { __rc });
+            } else {
+                let ty = &field.ty;
+                params.push(quote! { #ident: #ty });
+                values.push(quote! { #ident });
+            }
+        }
+
+        let construct = match &variant.fields {
+            Fields::Unit => quote! { #name::#variant_name },
+            Fields::Unnamed(_) => quote! { #name::#variant_name(#(#values),*) },
+            Fields::Named(_) => {
+                let names = variant.fields.iter().map(|f| &f.ident);
+                quote! { #name::#variant_name { #(#names: #values),* } }
+            }
+        };
+
+        match reply {
+            Some(t) => {
+                let sig = quote! {
+                    fn #method(&self, #(#params),*) -> impl ::core::future::Future<Output = ::core::result::Result<#t, ::mailboxxy::MailboxError>> + ::core::marker::Send + '_
+                };
+                bodies.push(quote! {
+                    #sig {
+                        return self.ask(move |__rc| #construct);
+                    }
+                });
+                signatures.push(sig);
+            }
+            None => {
+                let sig = quote! {
+                    fn #method(&self, #(#params),*) -> ::core::result::Result<(), ::mailboxxy::MailboxError>
+                };
+                bodies.push(quote! {
+                    #sig {
+                        return self.post(#construct);
+                    }
+                });
+                signatures.push(sig);
+            }
+        }
+    }
+
+    let doc = format!("Client methods for `Address<{}>`, generated by `#[derive(Mailbox)]`.", name);
+
+    return Ok(quote! {
+        #[doc = #doc]
+        #vis trait #trait_name {
+            #(#signatures;)*
+        }
+
+        impl #trait_name for ::mailboxxy::Address<#name> {
+            #(#bodies)*
+        }
+    });
+}
+
+/// `Some(T)` if `ty` is `ReplyChannel<T>` (with or without a path in front).
+fn reply_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let last = path.path.segments.last()?;
+    if last.ident != "ReplyChannel" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &last.arguments else { return None };
+    return match args.args.first() {
+        Some(GenericArgument::Type(t)) if args.args.len() == 1 => Some(t),
+        _ => None,
+    };
+}
+
+/// Methods of `Address` and `MailBox`, including those of their trait impls, which take precedence
+/// over the generated trait methods. Checked against the `mailboxxy` sources by its `test_derive_reserved_names`.
+const RESERVED: &[&str] = &[
+    "address", "ask", "ask_deadline", "ask_result", "ask_stream", "ask_timeout", "clone", "close", "deref",
+    "downgrade", "dropped", "exit", "id", "is_closed", "kill", "ping", "post", "post_after", "post_async",
+    "post_interval", "shutdown", "sink", "stats", "stop", "try_post",
+];
+
+fn method_name(variant: &Ident) -> Ident {
+    let name = snake_case(&variant.to_string());
+    // e.g. a variant `Type` becomes `r#type`
+    if syn::parse_str::<Ident>(&name).is_err() {
+        return Ident::new_raw(&name, variant.span());
+    }
+    return Ident::new(&name, variant.span());
+}
+
+/// `GetValue` -> `get_value`, `HTTPRequest` -> `http_request`
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev_lower = i > 0 && (chars[i - 1].is_lowercase() || chars[i - 1].is_ascii_digit());
+            let next_lower = i > 0 && chars[i - 1].is_uppercase() && chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev_lower || next_lower {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    return out;
+}
@@ -0,0 +1,55 @@
+use mailboxxy::*;
+
+
+#[derive(Mailbox)]
+enum CounterMsg {
+    Increment,
+    Decrement(i32),
+    Set { value: i32 },
+    GetValue(ReplyChannel<i32>),
+    Add { n: i32, result: ReplyChannel<i32> },
+    IsAbove(i32, ReplyChannel<bool>),
+}
+
+async fn counter(ctx: MailboxContext<CounterMsg>) {
+    let mut count = 0;
+    while let Some(msg) = ctx.dequeue().await {
+        match msg {
+            CounterMsg::Increment => count += 1,
+            CounterMsg::Decrement(n) => count -= n,
+            CounterMsg::Set { value } => count = value,
+            CounterMsg::GetValue(rc) => { let _ = rc.reply(count); }
+            CounterMsg::Add { n, result } => {
+                count += n;
+                let _ = result.reply(count);
+            }
+            CounterMsg::IsAbove(n, rc) => { let _ = rc.reply(count > n); }
+        }
+    }
+}
+
+struct TestSpawner;
+
+impl Spawner for TestSpawner {
+    fn spawn(&self, fut: BoxFuture) {
+        smol::spawn(fut).detach();
+    }
+}
+
+#[test]
+fn test_derive() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, counter, TestSpawner);
+
+        mb.increment().unwrap();
+        mb.increment().unwrap();
+        mb.decrement(3).unwrap();
+        assert_eq!(mb.get_value().await, Ok(-1));
+
+        mb.set(10).unwrap();
+        assert_eq!(mb.add(5).await, Ok(15));
+
+        let address = mb.address();
+        assert_eq!(address.is_above(14).await, Ok(true));
+    });
+}
@@ -0,0 +1,110 @@
+use std::future::Future;
+
+use crate::{start_mailbox, MailBox, MailboxBounds, MailboxContext, Spawner};
+
+
+/// An actor whose state lives in a struct, as an alternative to writing the receive loop by hand.
+///
+/// `start_actor` calls `started`, then `handle` for every message until the mailbox is closed
+/// and drained, then `stopping` and `stopped`. The hooks do nothing by default.
+/// If `handle` panics, the actor is gone without calling `stopping`/`stopped`.
+pub trait Actor : Send + Sized + 'static {
+    type Msg : Send + 'static;
+
+    fn handle(&mut self, msg: Self::Msg, ctx: &MailboxContext<Self::Msg>) -> impl Future<Output = ()> + Send;
+
+    /// Called before the first message is handled.
+    fn started(&mut self, ctx: &MailboxContext<Self::Msg>) -> impl Future<Output = ()> + Send {
+        let _ = ctx;
+        async {}
+    }
+
+    /// Called after the last message was handled, the mailbox is already closed at this point.
+    fn stopping(&mut self, ctx: &MailboxContext<Self::Msg>) -> impl Future<Output = ()> + Send {
+        let _ = ctx;
+        async {}
+    }
+
+    /// Called last, consuming the actor.
+    fn stopped(self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+pub fn start_actor<A, S>(mut actor: A, bounds: MailboxBounds<A::Msg>, spawner: S) -> MailBox<A::Msg>
+where
+    A : Actor,
+    S : Spawner
+{
+    return start_mailbox(bounds, move |ctx: MailboxContext<A::Msg>| async move {
+        actor.started(&ctx).await;
+        while let Some(msg) = ctx.dequeue().await {
+            actor.handle(msg, &ctx).await;
+        }
+        actor.stopping(&ctx).await;
+        drop(ctx);
+        actor.stopped().await;
+    }, spawner);
+}
+
+
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+
+#[cfg(test)]
+use crate::{ReplyChannel, TestSpawner};
+
+#[cfg(test)]
+struct Counter {
+    count: i32,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+#[cfg(test)]
+enum CounterMsg {
+    Increment,
+    Stop,
+    GetValue(ReplyChannel<i32>),
+}
+
+#[cfg(test)]
+impl Actor for Counter {
+    type Msg = CounterMsg;
+
+    async fn started(&mut self, _ctx: &MailboxContext<CounterMsg>) {
+        self.log.lock().unwrap().push("started".to_string());
+    }
+
+    async fn handle(&mut self, msg: CounterMsg, ctx: &MailboxContext<CounterMsg>) {
+        match msg {
+            CounterMsg::Increment => self.count += 1,
+            CounterMsg::Stop => ctx.close(),
+            CounterMsg::GetValue(rc) => { let _ = rc.reply(self.count); }
+        }
+    }
+
+    async fn stopping(&mut self, _ctx: &MailboxContext<CounterMsg>) {
+        self.log.lock().unwrap().push(format!("stopping at {}", self.count));
+    }
+
+    async fn stopped(self) {
+        self.log.lock().unwrap().push("stopped".to_string());
+    }
+}
+
+#[test]
+fn test_actor() {
+    smol::block_on(async {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mb = start_actor(Counter { count: 0, log: log.clone() }, MailboxBounds::Unbounded, TestSpawner);
+
+        mb.post(CounterMsg::Increment).unwrap();
+        mb.post(CounterMsg::Increment).unwrap();
+        assert_eq!(mb.ask(CounterMsg::GetValue).await, Ok(2));
+
+        // the actor closes its own mailbox
+        mb.post(CounterMsg::Stop).unwrap();
+        assert!(mb.shutdown().await.is_normal());
+        assert_eq!(*log.lock().unwrap(), vec!["started", "stopping at 2", "stopped"]);
+    });
+}
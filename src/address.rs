@@ -0,0 +1,204 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_channel::{Sender, TrySendError, WeakSender};
+
+use crate::{ActorExit, AskError, Control, ExitCell, MailboxError, Overflowing, ReplyChannel, TimerHandle, TryPostError};
+
+
+/// A cheap, cloneable reference to an actor's mailbox.
+///
+/// The actor keeps running as long as at least one `Address` (or its `MailBox`) is alive.
+pub struct Address<TMessage> {
+    sender: Sender<TMessage>,
+    pub(crate) control: Sender<Control>,
+    pub(crate) exit: Arc<ExitCell>,
+    // `None` unless the mailbox has an overflow policy other than `Overflow::Block`
+    overflow: Option<Arc<Overflowing<TMessage>>>,
+}
+
+impl<TMessage> Clone for Address<TMessage> {
+    fn clone(&self) -> Self {
+        return Address {
+            sender: self.sender.clone(),
+            control: self.control.clone(),
+            exit: self.exit.clone(),
+            overflow: self.overflow.clone(),
+        };
+    }
+}
+
+impl<TMessage> Address<TMessage> {
+    pub(crate) fn new(sender: Sender<TMessage>, control: Sender<Control>, exit: Arc<ExitCell>, overflow: Option<Arc<Overflowing<TMessage>>>) -> Self {
+        return Address { sender, control, exit, overflow };
+    }
+
+    /// Returns a reference which doesn't keep the actor alive.
+    pub fn downgrade(&self) -> WeakAddress<TMessage> {
+        return WeakAddress {
+            sender: self.sender.downgrade(),
+            control: self.control.clone(),
+            exit: self.exit.clone(),
+            overflow: self.overflow.clone(),
+        };
+    }
+
+    /// How many messages the overflow policy has dropped or rejected so far,
+    /// always 0 without one (see `MailboxBounds::BoundedWith`).
+    pub fn dropped(&self) -> u64 {
+        return self.overflow.as_ref().map_or(0, |overflow| overflow.dropped());
+    }
+
+    /// `None` while the actor is still running.
+    pub fn exit(&self) -> Option<ActorExit> {
+        return self.exit.get();
+    }
+
+    /// The error for an `ask` whose reply channel was dropped (or couldn't be sent at all).
+    pub(crate) fn ask_error(&self, e: MailboxError) -> MailboxError {
+        return self.exit.ask_error(e);
+    }
+
+    /// Blocks the current thread while a bounded mailbox is full, use `post_async` from async code.
+    ///
+    /// With an overflow policy, the policy applies instead and this never blocks.
+    pub fn post(&self, msg: TMessage) -> Result<(), MailboxError> {
+        if self.overflow.is_some() {
+            return self.try_post(msg).map_err(|e| {
+                let err = MailboxError::from(&e);
+                crate::discard(e.into_inner());
+                err
+            });
+        }
+        return self.sender.send_blocking(msg).map_err(|e| {
+            crate::discard(e.into_inner());
+            MailboxError::Closed
+        });
+    }
+
+    /// Waits asynchronously until there is room in the mailbox, or applies the overflow policy.
+    pub async fn post_async(&self, msg: TMessage) -> Result<(), MailboxError> {
+        if self.overflow.is_some() {
+            return self.post(msg);
+        }
+        return self.sender.send(msg).await.map_err(|e| {
+            crate::discard(e.into_inner());
+            MailboxError::Closed
+        });
+    }
+
+    /// Never waits, if the message can't be enqueued right now it is handed back inside the error.
+    pub fn try_post(&self, msg: TMessage) -> Result<(), TryPostError<TMessage>> {
+        if let Some(overflow) = &self.overflow {
+            return overflow.try_post(&self.sender, msg);
+        }
+        return self.sender.try_send(msg).map_err(|e| match e {
+            TrySendError::Full(msg) => TryPostError::Full(msg),
+            TrySendError::Closed(msg) => TryPostError::Closed(msg),
+        });
+    }
+
+    pub async fn ask<TResult, F>(&self, cb: F) -> Result<TResult, MailboxError>
+    where
+        F : FnOnce(ReplyChannel<TResult>) -> TMessage
+    {
+        let (rc, r) = ReplyChannel::new(std::any::type_name::<TMessage>());
+        let msg = cb(rc);
+        self.post_async(msg).await.map_err(|e| self.ask_error(e))?;
+        return r.await.map_err(|e| self.ask_error(e));
+    }
+
+    /// `ask` for actors replying with a `Result`, flattening the transport error and the actor's error
+    /// into one `AskError`.
+    pub async fn ask_result<TResult, E, F>(&self, cb: F) -> Result<TResult, AskError<E>>
+    where
+        F : FnOnce(ReplyChannel<Result<TResult, E>>) -> TMessage
+    {
+        return match self.ask(cb).await {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(e)) => Err(AskError::Actor(e)),
+            Err(e) => Err(AskError::Mailbox(e)),
+        };
+    }
+
+    /// `ask`, but fails with `MailboxError::Timeout` if there's no reply within `timeout`.
+    pub async fn ask_timeout<TResult, F>(&self, timeout: Duration, cb: F) -> Result<TResult, MailboxError>
+    where
+        F : FnOnce(ReplyChannel<TResult>) -> TMessage
+    {
+        return crate::timeout(timeout, self.ask(cb)).await
+            .unwrap_or(Err(MailboxError::Timeout));
+    }
+
+    /// `ask`, but fails with `MailboxError::Timeout` if there's no reply by `deadline`.
+    pub async fn ask_deadline<TResult, F>(&self, deadline: Instant, cb: F) -> Result<TResult, MailboxError>
+    where
+        F : FnOnce(ReplyChannel<TResult>) -> TMessage
+    {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        return self.ask_timeout(timeout, cb).await;
+    }
+
+    /// Posts `msg` after `delay`. If the mailbox is full at that point, the message is dropped.
+    ///
+    /// The timer doesn't keep the actor alive.
+    pub fn post_after(&self, delay: Duration, msg: TMessage) -> TimerHandle
+    where
+        TMessage : Send + 'static
+    {
+        return crate::timer::post_after(self.downgrade(), delay, msg);
+    }
+
+    /// Posts `f()` every `period` until the handle is canceled or the mailbox is closed.
+    /// Ticks are skipped while the mailbox is full.
+    ///
+    /// `f` runs on the timer thread all timers share, so it should return quickly and must not block.
+    ///
+    /// Panics if `period` is zero.
+    pub fn post_interval<F>(&self, period: Duration, f: F) -> TimerHandle
+    where
+        TMessage : Send + 'static,
+        F : FnMut() -> TMessage + Send + 'static
+    {
+        return crate::timer::post_interval(self.downgrade(), period, f);
+    }
+
+    /// Stops accepting new messages. Messages already in the mailbox are still delivered,
+    /// after that `MailboxContext::dequeue` returns `None`.
+    pub fn close(&self) {
+        self.sender.close();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        return self.sender.is_closed();
+    }
+}
+
+
+/// A reference to an actor's mailbox that doesn't keep the actor alive,
+/// e.g. to break reference cycles between actors.
+pub struct WeakAddress<TMessage> {
+    sender: WeakSender<TMessage>,
+    // doesn't keep the actor alive, that's only up to `sender`
+    pub(crate) control: Sender<Control>,
+    pub(crate) exit: Arc<ExitCell>,
+    overflow: Option<Arc<Overflowing<TMessage>>>,
+}
+
+impl<TMessage> Clone for WeakAddress<TMessage> {
+    fn clone(&self) -> Self {
+        return WeakAddress {
+            sender: self.sender.clone(),
+            control: self.control.clone(),
+            exit: self.exit.clone(),
+            overflow: self.overflow.clone(),
+        };
+    }
+}
+
+impl<TMessage> WeakAddress<TMessage> {
+    /// Returns `None` once every `Address` and the `MailBox` have been dropped.
+    pub fn upgrade(&self) -> Option<Address<TMessage>> {
+        return self.sender.upgrade().map(|sender| Address::new(sender, self.control.clone(), self.exit.clone(), self.overflow.clone()));
+    }
+}
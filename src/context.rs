@@ -0,0 +1,384 @@
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_channel::Receiver;
+use futures_util::future::{select, Either};
+#[cfg(feature = "futures")]
+use futures_util::Stream;
+#[cfg(feature = "futures")]
+use std::pin::Pin;
+#[cfg(feature = "futures")]
+use std::task::{Context, Poll};
+
+use crate::{Control, Lanes, MailboxError, MailboxStats, Priority, TimerHandle, WeakAddress};
+
+
+pub(crate) type PriorityFn<TMessage> = fn(&TMessage) -> Priority;
+
+/// The receiving end of a mailbox, shared by all incarnations of a (supervised) actor.
+pub(crate) struct Inbox<TMessage> {
+    receiver: Receiver<TMessage>,
+    control: Receiver<Control>,
+    // set by `Control::Stop`, from then on `dequeue` returns `None`
+    stopped: AtomicBool,
+    received: AtomicU64,
+    // for timers the actor schedules for itself, mustn't keep the actor alive
+    pub(crate) address: WeakAddress<TMessage>,
+    // `None` delivers strictly in order, see `start_prioritized_mailbox`
+    priority: Option<PriorityFn<TMessage>>,
+    // set aside by `scan`, or taken out of `receiver` to be sorted by priority;
+    // delivered before anything still in `receiver`
+    pending: Mutex<Lanes<TMessage>>,
+}
+
+impl<TMessage> Inbox<TMessage> {
+    pub(crate) fn new(
+        receiver: Receiver<TMessage>,
+        control: Receiver<Control>,
+        address: WeakAddress<TMessage>,
+        priority: Option<PriorityFn<TMessage>>
+    ) -> Arc<Self> {
+        return Arc::new(Inbox {
+            receiver,
+            control,
+            stopped: AtomicBool::new(false),
+            received: AtomicU64::new(0),
+            address,
+            priority,
+            pending: Mutex::new(Lanes::new()),
+        });
+    }
+
+    /// Drops everything still queued, so pending `ask`s fail instead of waiting forever.
+    pub(crate) fn close_and_drain(&self) {
+        self.receiver.close();
+        self.control.close();
+        while let Ok(msg) = self.receiver.try_recv() {
+            crate::discard(msg);
+        }
+        while let Ok(control) = self.control.try_recv() {
+            crate::discard(control);
+        }
+        crate::discard(self.pending.lock().unwrap().take_all());
+    }
+
+    fn handle_control(&self, control: Control) {
+        match control {
+            Control::Stop => {
+                self.stopped.store(true, Ordering::SeqCst);
+                self.receiver.close();
+            }
+            Control::Ping(rc) => { let _ = rc.reply(()); }
+            Control::GetStats(rc) => {
+                let stats = MailboxStats {
+                    received: self.received.load(Ordering::Relaxed),
+                    queued: self.receiver.len() + self.pending.lock().unwrap().len(),
+                    dropped: self.address.upgrade().map_or(0, |address| address.dropped()),
+                };
+                let _ = rc.reply(stats);
+            }
+            Control::Wake => {}
+        }
+    }
+
+    /// Handles everything waiting on the control lane, returns whether the actor should stop.
+    fn poll_control(&self) -> bool {
+        while let Ok(control) = self.control.try_recv() {
+            self.handle_control(control);
+        }
+        return self.stopped.load(Ordering::SeqCst);
+    }
+
+    /// Delivers `msg` ahead of everything queued, regardless of the mailbox bounds.
+    pub(crate) fn notify(&self, msg: TMessage) {
+        self.pending.lock().unwrap().push(Priority::High, msg);
+        // gets `next` out of waiting on `receiver`
+        let _ = self.address.control.try_send(Control::Wake);
+    }
+
+    fn count(&self, msg: Option<TMessage>) -> Option<TMessage> {
+        if msg.is_some() {
+            self.received.fetch_add(1, Ordering::Relaxed);
+        }
+        return msg;
+    }
+
+    fn set_aside(&self, msg: TMessage) {
+        let priority = self.priority.map_or(Priority::Normal, |f| f(&msg));
+        self.pending.lock().unwrap().push(priority, msg);
+    }
+
+    /// The next pending message. With priorities, first sorts whatever is in `receiver` into the lanes,
+    /// but takes at most as many as the mailbox can hold, so a bounded mailbox still pushes back.
+    fn next_pending(&self) -> Option<TMessage> {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(priority) = self.priority {
+            let limit = self.receiver.capacity().unwrap_or(usize::MAX);
+            while pending.len() < limit {
+                let Ok(msg) = self.receiver.try_recv() else { break };
+                pending.push(priority(&msg), msg);
+            }
+        }
+        return pending.pop();
+    }
+
+    /// Waits for the next message in `receiver`, `Err(MailboxError::Closed)` once it's closed and empty.
+    /// If a control message arrives first, handles it and returns `Ok(None)`, so the caller checks
+    /// `poll_control` and `pending` again.
+    async fn recv_or_control(&self) -> Result<Option<TMessage>, MailboxError> {
+        return match select(pin!(self.receiver.recv()), pin!(self.control.recv())).await {
+            Either::Left((msg, _)) => Ok(Some(msg.map_err(|_| MailboxError::Closed)?)),
+            Either::Right((Ok(control), _)) => {
+                self.handle_control(control);
+                Ok(None)
+            }
+            // only after `close_and_drain`
+            Either::Right((Err(_), _)) => Ok(Some(self.receiver.recv().await.map_err(|_| MailboxError::Closed)?)),
+        };
+    }
+
+    /// The next message, `None` once the mailbox is closed and drained, or the actor was stopped.
+    /// Handles the control lane while waiting.
+    async fn next(&self) -> Option<TMessage> {
+        loop {
+            if self.poll_control() {
+                return None;
+            }
+            if let Some(msg) = self.next_pending() {
+                return Some(msg);
+            }
+            let Some(msg) = self.recv_or_control().await.ok()? else { continue };
+            if self.priority.is_none() {
+                return Some(msg);
+            }
+            // more may have arrived meanwhile, which might be more urgent
+            self.set_aside(msg);
+        }
+    }
+}
+
+
+pub struct MailboxContext<TMessage> {
+    inbox: Arc<Inbox<TMessage>>,
+    // receivers of our own, so polling the stream can keep their listeners between polls
+    #[cfg(feature = "futures")]
+    stream: Pin<Box<Receiver<TMessage>>>,
+    #[cfg(feature = "futures")]
+    control: Pin<Box<Receiver<Control>>>,
+}
+
+impl<TMessage> MailboxContext<TMessage> {
+    pub(crate) fn new(inbox: Arc<Inbox<TMessage>>) -> Self {
+        #[cfg(feature = "futures")]
+        let (stream, control) = (Box::pin(inbox.receiver.clone()), Box::pin(inbox.control.clone()));
+        return MailboxContext {
+            inbox,
+            #[cfg(feature = "futures")]
+            stream,
+            #[cfg(feature = "futures")]
+            control,
+        };
+    }
+
+    pub(crate) fn inbox(&self) -> &Arc<Inbox<TMessage>> {
+        return &self.inbox;
+    }
+
+    /// Stops accepting new messages, like `Address::close`. Messages already in the mailbox
+    /// are still returned by `dequeue`.
+    pub fn close(&self) {
+        self.inbox.receiver.close();
+    }
+
+    /// Returns `None` once the mailbox has been closed (or every `MailBox` dropped) and drained.
+    pub async fn dequeue(&self) -> Option<TMessage> {
+        return self.inbox.count(self.inbox.next().await);
+    }
+
+    /// `dequeue`, but fails with `MailboxError::Timeout` if nothing arrives within `timeout`,
+    /// or `MailboxError::Closed` once the mailbox is closed and drained.
+    pub async fn receive_timeout(&self, timeout: Duration) -> Result<TMessage, MailboxError> {
+        return match crate::timeout(timeout, self.dequeue()).await {
+            Some(Some(msg)) => Ok(msg),
+            Some(None) => Err(MailboxError::Closed),
+            None => Err(MailboxError::Timeout),
+        };
+    }
+
+    /// `dequeue`, but returns `None` if nothing arrives within `timeout`.
+    pub async fn try_receive(&self, timeout: Duration) -> Option<TMessage> {
+        return crate::timeout(timeout, self.dequeue()).await.flatten();
+    }
+
+    /// Waits for the first message matching `predicate`. Messages which don't match
+    /// stay in the mailbox, in order, and are returned by later calls to `dequeue` or `scan`.
+    ///
+    /// Set-aside messages are moved out of the mailbox's channel, so they no longer count against
+    /// `MailboxBounds::Bounded`: senders can fill it up again while the actor is still scanning.
+    ///
+    /// Returns `None` once the mailbox is closed and no matching message is left, or the actor was stopped.
+    /// Handles the control lane while waiting, like `dequeue`.
+    pub async fn scan<P>(&self, mut predicate: P) -> Option<TMessage>
+    where
+        P : FnMut(&TMessage) -> bool
+    {
+        loop {
+            if self.inbox.poll_control() {
+                return None;
+            }
+            // again after every wake-up, `MailboxContext::monitor` delivers into `pending`
+            if let Some(msg) = self.inbox.pending.lock().unwrap().take_first(&mut predicate) {
+                return self.inbox.count(Some(msg));
+            }
+            let Some(msg) = self.inbox.recv_or_control().await.ok()? else { continue };
+            if predicate(&msg) {
+                return self.inbox.count(Some(msg));
+            }
+            self.inbox.set_aside(msg);
+        }
+    }
+
+    /// `scan`, but returns `None` if no matching message arrives within `timeout`.
+    /// Like with `scan`, messages set aside meanwhile don't count against the mailbox bounds.
+    pub async fn try_scan<P>(&self, timeout: Duration, predicate: P) -> Option<TMessage>
+    where
+        P : FnMut(&TMessage) -> bool
+    {
+        return crate::timeout(timeout, self.scan(predicate)).await.flatten();
+    }
+
+    /// Posts `msg` to this actor's own mailbox after `delay`, see `Address::post_after`.
+    pub fn schedule_self(&self, delay: Duration, msg: TMessage) -> TimerHandle
+    where
+        TMessage : Send + 'static
+    {
+        return crate::timer::post_after(self.inbox.address.clone(), delay, msg);
+    }
+
+    /// Posts `f()` to this actor's own mailbox every `period`, see `Address::post_interval`.
+    pub fn schedule_self_interval<F>(&self, period: Duration, f: F) -> TimerHandle
+    where
+        TMessage : Send + 'static,
+        F : FnMut() -> TMessage + Send + 'static
+    {
+        return crate::timer::post_interval(self.inbox.address.clone(), period, f);
+    }
+}
+
+
+/// Yields the same messages as `dequeue` and ends once the mailbox is closed and drained.
+///
+/// With `StreamExt` in scope, `ctx.scan(..)` resolves to `StreamExt::scan`,
+/// call `MailboxContext::scan(&ctx, ..)` to get the one above.
+#[cfg(feature = "futures")]
+impl<TMessage> Stream for MailboxContext<TMessage> {
+    type Item = TMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<TMessage>> {
+        loop {
+            while let Poll::Ready(Some(control)) = self.control.as_mut().poll_next(cx) {
+                self.inbox.handle_control(control);
+            }
+            if self.inbox.poll_control() {
+                return Poll::Ready(None);
+            }
+            if let Some(msg) = self.inbox.next_pending() {
+                return Poll::Ready(self.inbox.count(Some(msg)));
+            }
+            let msg = std::task::ready!(self.stream.as_mut().poll_next(cx));
+            if self.inbox.priority.is_none() {
+                return Poll::Ready(self.inbox.count(msg));
+            }
+            match msg {
+                Some(msg) => self.inbox.set_aside(msg),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+use crate::{start_mailbox, MailboxBounds, ReplyChannel, TestSpawner};
+
+#[cfg(test)]
+enum Msg {
+    Data(i32),
+    Reply(i32),
+    Get(ReplyChannel<Vec<i32>>),
+}
+
+#[test]
+fn test_scan() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<Msg>| async move {
+            let mut seen = Vec::new();
+
+            // wait for the reply first, everything else has to keep its order
+            if let Some(Msg::Reply(n)) = ctx.scan(|m| matches!(m, Msg::Reply(_))).await {
+                seen.push(n);
+            }
+            assert!(ctx.try_scan(Duration::from_millis(10), |m| matches!(m, Msg::Reply(_))).await.is_none());
+            assert!(ctx.receive_timeout(Duration::from_millis(10)).await.is_ok());
+
+            while let Some(msg) = ctx.dequeue().await {
+                match msg {
+                    Msg::Data(n) | Msg::Reply(n) => seen.push(n),
+                    Msg::Get(rc) => { let _ = rc.reply(seen.clone()); }
+                }
+            }
+        }, TestSpawner);
+
+        mb.post(Msg::Data(1)).unwrap();
+        mb.post(Msg::Data(2)).unwrap();
+        mb.post(Msg::Data(3)).unwrap();
+        mb.post(Msg::Reply(10)).unwrap();
+
+        // Data(1) was taken by receive_timeout
+        assert_eq!(mb.ask(Msg::Get).await, Ok(vec![10, 2, 3]));
+    });
+}
+
+#[test]
+fn test_receive_timeout() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<Msg>| async move {
+            assert_eq!(ctx.receive_timeout(Duration::from_millis(10)).await.err(), Some(MailboxError::Timeout));
+            assert!(ctx.try_receive(Duration::from_millis(10)).await.is_none());
+            assert_eq!(ctx.receive_timeout(Duration::from_secs(5)).await.err(), Some(MailboxError::Closed));
+        }, TestSpawner);
+
+        futures_timer::Delay::new(Duration::from_millis(50)).await;
+        assert!(mb.shutdown().await.is_normal());
+    });
+}
+
+#[cfg(all(test, feature = "futures"))]
+#[test]
+fn test_stream() {
+    use futures_util::StreamExt;
+
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<Msg>| async move {
+            // set aside by scan, the stream has to yield it first
+            let reply = MailboxContext::scan(&ctx, |m| matches!(m, Msg::Reply(_))).await;
+            assert!(matches!(reply, Some(Msg::Reply(10))));
+
+            let data: Vec<i32> = ctx.filter_map(|msg| async move {
+                match msg {
+                    Msg::Data(n) => Some(n),
+                    _ => None,
+                }
+            }).collect().await;
+            assert_eq!(data, vec![1, 2]);
+        }, TestSpawner);
+
+        mb.post(Msg::Data(1)).unwrap();
+        mb.post(Msg::Reply(10)).unwrap();
+        mb.post(Msg::Data(2)).unwrap();
+        mb.close();
+        assert!(mb.shutdown().await.is_normal());
+    });
+}
@@ -0,0 +1,139 @@
+use crate::{Address, MailboxError, ReplyChannel};
+
+
+/// Messages on the control lane every mailbox has next to its own messages.
+///
+/// `MailboxContext::dequeue` handles them before any queued message, the actor never sees them.
+pub(crate) enum Control {
+    Stop,
+    Ping(ReplyChannel<()>),
+    GetStats(ReplyChannel<MailboxStats>),
+    // something was put into the pending lanes, see `MailboxContext::monitor`
+    Wake,
+}
+
+/// Returned by `Address::stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MailboxStats {
+    /// Messages the actor has dequeued so far.
+    pub received: u64,
+    /// Messages waiting in the mailbox.
+    pub queued: usize,
+    /// Messages dropped or rejected by the overflow policy, see `Address::dropped`.
+    pub dropped: u64,
+}
+
+impl<TMessage> Address<TMessage> {
+    /// Asks the actor to stop: its next `dequeue` returns `None`, even if there are still messages
+    /// in the mailbox, which are dropped. Use `close` to handle those first.
+    pub fn stop(&self) -> Result<(), MailboxError> {
+        return self.control.try_send(Control::Stop).map_err(|_| MailboxError::Closed);
+    }
+
+    /// Stops the actor right away, even in the middle of handling a message, without it getting
+    /// a chance to clean up. It exits with `ActorExit::Killed`; supervised actors are restarted.
+    pub fn kill(&self) {
+        self.exit.kill();
+    }
+
+    /// Resolves once the actor is waiting in `dequeue` again, e.g. to check that it's not stuck.
+    pub async fn ping(&self) -> Result<(), MailboxError> {
+        return self.control(Control::Ping).await;
+    }
+
+    /// Answered by the actor on its next `dequeue`, like `ping`.
+    pub async fn stats(&self) -> Result<MailboxStats, MailboxError> {
+        return self.control(Control::GetStats).await;
+    }
+
+    async fn control<T, F>(&self, cb: F) -> Result<T, MailboxError>
+    where
+        F : FnOnce(ReplyChannel<T>) -> Control
+    {
+        let (rc, r) = ReplyChannel::new(std::any::type_name::<Control>());
+        if let Err(e) = self.control.try_send(cb(rc)) {
+            crate::discard(e.into_inner());
+            return Err(MailboxError::Closed);
+        }
+        return r.await.map_err(|e| self.ask_error(e));
+    }
+}
+
+
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::{start_mailbox, ActorExit, MailboxBounds, MailboxContext, TestSpawner};
+
+#[test]
+fn test_control() {
+    smol::block_on(async {
+        let (busy_s, busy_r) = async_channel::bounded::<()>(1);
+        let (go_s, go_r) = async_channel::bounded::<()>(1);
+        let mb = start_mailbox(MailboxBounds::Unbounded, move |ctx: MailboxContext<i32>| async move {
+            let mut seen = Vec::new();
+            while let Some(n) = ctx.dequeue().await {
+                seen.push(n);
+                let _ = busy_s.send(()).await;
+                if n == 2 {
+                    let _ = go_r.recv().await;
+                }
+            }
+            assert_eq!(seen, vec![1, 2]);
+        }, TestSpawner);
+
+        // the ping could overtake 1, so wait until the actor has it
+        mb.post(1).unwrap();
+        busy_r.recv().await.unwrap();
+        assert_eq!(mb.ping().await, Ok(()));
+        assert_eq!(mb.stats().await, Ok(MailboxStats { received: 1, queued: 0, dropped: 0 }));
+
+        // the actor is busy with 2, 3 and 4 wait behind it, but the stop overtakes them
+        mb.post(2).unwrap();
+        busy_r.recv().await.unwrap();
+        mb.post(3).unwrap();
+        mb.post(4).unwrap();
+        mb.stop().unwrap();
+        go_s.send(()).await.unwrap();
+        assert!(mb.shutdown().await.is_normal());
+    });
+}
+
+#[test]
+fn test_kill() {
+    smol::block_on(async {
+        let (busy_s, busy_r) = async_channel::bounded::<()>(1);
+        let mb = start_mailbox(MailboxBounds::Unbounded, move |ctx: MailboxContext<i32>| async move {
+            while ctx.dequeue().await.is_some() {
+                // stuck, only a kill gets it out of here
+                let _ = busy_s.send(()).await;
+                std::future::pending::<()>().await;
+            }
+        }, TestSpawner);
+
+        mb.post(1).unwrap();
+        busy_r.recv().await.unwrap();
+        assert_eq!(crate::timeout(Duration::from_millis(20), mb.ping()).await, None);
+        mb.kill();
+        assert!(matches!(mb.handle.await, ActorExit::Killed));
+    });
+}
+
+#[test]
+fn test_control_while_scanning() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<i32>| async move {
+            // waits for a message which never comes
+            assert_eq!(ctx.scan(|n| *n == 0).await, None);
+            // the stop also ends a later `dequeue`, the queued 1 is dropped
+            assert_eq!(ctx.dequeue().await, None);
+        }, TestSpawner);
+
+        mb.post(1).unwrap();
+        assert_eq!(crate::timeout(Duration::from_secs(5), mb.ping()).await, Some(Ok(())));
+        assert_eq!(mb.stats().await, Ok(MailboxStats { received: 0, queued: 1, dropped: 0 }));
+        mb.stop().unwrap();
+        assert!(matches!(crate::timeout(Duration::from_secs(5), mb.handle).await, Some(ActorExit::Normal)));
+    });
+}
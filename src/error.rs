@@ -0,0 +1,140 @@
+use std::fmt;
+
+
+/// Why a message could not be delivered to (or answered by) an actor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MailboxError {
+    /// The actor is no longer receiving messages.
+    Closed,
+    /// The mailbox is bounded and currently at capacity.
+    Full,
+    /// The actor dropped the `ReplyChannel` without replying.
+    NoReply,
+    /// The actor didn't reply in time.
+    Timeout,
+    /// The actor panicked before replying.
+    ActorPanicked,
+}
+
+impl fmt::Display for MailboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MailboxError::Closed => write!(f, "mailbox closed"),
+            MailboxError::Full => write!(f, "mailbox full"),
+            MailboxError::NoReply => write!(f, "reply channel dropped without reply"),
+            MailboxError::Timeout => write!(f, "timed out waiting for reply"),
+            MailboxError::ActorPanicked => write!(f, "actor panicked"),
+        }
+    }
+}
+
+impl std::error::Error for MailboxError {}
+
+
+/// Returned by `ask_result`, keeps transport errors apart from the actor's own errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AskError<E> {
+    /// The message couldn't be delivered or wasn't answered.
+    Mailbox(MailboxError),
+    /// The actor answered with an error.
+    Actor(E),
+}
+
+impl<E: fmt::Display> fmt::Display for AskError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AskError::Mailbox(e) => e.fmt(f),
+            AskError::Actor(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for AskError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AskError::Mailbox(e) => Some(e),
+            AskError::Actor(e) => Some(e),
+        }
+    }
+}
+
+impl<E> From<MailboxError> for AskError<E> {
+    fn from(e: MailboxError) -> Self {
+        return AskError::Mailbox(e);
+    }
+}
+
+
+/// Returned by the `Registry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryError {
+    /// Another actor, which is still running, is registered under the name.
+    AlreadyRegistered,
+    /// No actor is registered under the name, or it has exited.
+    NotFound,
+    /// The actor registered under the name has a different message type.
+    WrongType,
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::AlreadyRegistered => write!(f, "name already registered"),
+            RegistryError::NotFound => write!(f, "no actor registered under that name"),
+            RegistryError::WrongType => write!(f, "actor registered under that name has a different message type"),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+
+/// Returned by `try_post`, hands the message back to the caller.
+#[derive(PartialEq, Eq)]
+pub enum TryPostError<T> {
+    /// The mailbox is bounded and currently at capacity.
+    Full(T),
+    /// The actor is no longer receiving messages.
+    Closed(T),
+}
+
+impl<T> TryPostError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            TryPostError::Full(msg) => msg,
+            TryPostError::Closed(msg) => msg,
+        }
+    }
+}
+
+impl<T> fmt::Debug for TryPostError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryPostError::Full(_) => write!(f, "Full(..)"),
+            TryPostError::Closed(_) => write!(f, "Closed(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for TryPostError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return MailboxError::from(self).fmt(f);
+    }
+}
+
+impl<T> std::error::Error for TryPostError<T> {}
+
+impl<T> From<&TryPostError<T>> for MailboxError {
+    fn from(e: &TryPostError<T>) -> Self {
+        match e {
+            TryPostError::Full(_) => MailboxError::Full,
+            TryPostError::Closed(_) => MailboxError::Closed,
+        }
+    }
+}
+
+impl<T> From<TryPostError<T>> for MailboxError {
+    fn from(e: TryPostError<T>) -> Self {
+        return MailboxError::from(&e);
+    }
+}
@@ -0,0 +1,179 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use futures_util::future::{abortable, AbortHandle, Aborted};
+
+use crate::{ActorId, MailboxError};
+
+
+/// How an actor function finished.
+#[derive(Debug, Clone)]
+pub enum ActorExit {
+    /// The actor function returned (`()` or `Ok(())`).
+    Normal,
+    /// The actor panicked, with the panic message if there was one.
+    Panicked(String),
+    /// The actor function returned `Err(e)`.
+    Error(Arc<dyn Error + Send + Sync>),
+    /// The actor was stopped by `Address::kill`.
+    Killed,
+}
+
+impl ActorExit {
+    pub fn is_normal(&self) -> bool {
+        return matches!(self, ActorExit::Normal);
+    }
+
+    pub fn is_panicked(&self) -> bool {
+        return matches!(self, ActorExit::Panicked(_));
+    }
+
+    pub(crate) fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let msg = match payload.downcast::<String>() {
+            Ok(msg) => *msg,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(msg) => msg.to_string(),
+                Err(_) => "<non-string panic payload>".to_string(),
+            },
+        };
+        return ActorExit::Panicked(msg);
+    }
+}
+
+impl fmt::Display for ActorExit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActorExit::Normal => write!(f, "exited normally"),
+            ActorExit::Panicked(msg) => write!(f, "panicked: {}", msg),
+            ActorExit::Error(e) => write!(f, "failed: {}", e),
+            ActorExit::Killed => write!(f, "killed"),
+        }
+    }
+}
+
+
+/// Return types an actor function may have.
+pub trait ActorResult {
+    fn into_exit(self) -> ActorExit;
+}
+
+impl ActorResult for () {
+    fn into_exit(self) -> ActorExit {
+        return ActorExit::Normal;
+    }
+}
+
+impl ActorResult for ActorExit {
+    fn into_exit(self) -> ActorExit {
+        return self;
+    }
+}
+
+impl<E: Into<Box<dyn Error + Send + Sync>>> ActorResult for Result<(), E> {
+    fn into_exit(self) -> ActorExit {
+        return match self {
+            Ok(()) => ActorExit::Normal,
+            Err(e) => ActorExit::Error(Arc::from(e.into())),
+        };
+    }
+}
+
+
+type Watcher = Box<dyn FnOnce(&ActorExit) + Send>;
+
+/// Where an actor's exit is recorded, shared between its `JoinHandle` and `Address`es.
+pub(crate) struct ExitCell {
+    pub(crate) id: ActorId,
+    exit: Mutex<Option<ActorExit>>,
+    // monitors and links, called once the exit is set
+    watchers: Mutex<Vec<Watcher>>,
+    // aborts the currently running incarnation of the actor
+    kill: Mutex<Option<AbortHandle>>,
+}
+
+impl Default for ExitCell {
+    fn default() -> Self {
+        return ExitCell {
+            id: ActorId::next(),
+            exit: Mutex::new(None),
+            watchers: Mutex::new(Vec::new()),
+            kill: Mutex::new(None),
+        };
+    }
+}
+
+impl ExitCell {
+    /// Makes `fut` abortable through `kill`, resolving to `ActorExit::Killed` if it was.
+    pub(crate) fn killable<Fut>(&self, fut: Fut) -> impl Future<Output = ActorExit>
+    where
+        Fut : Future,
+        Fut::Output : ActorResult
+    {
+        let (fut, handle) = abortable(fut);
+        *self.kill.lock().unwrap() = Some(handle);
+        return async move {
+            let mut fut = Box::pin(fut);
+            return match fut.as_mut().await {
+                Ok(result) => result.into_exit(),
+                Err(Aborted) => {
+                    // the actor never got to answer the `ReplyChannel`s it was holding
+                    crate::discard(fut);
+                    ActorExit::Killed
+                }
+            };
+        };
+    }
+
+    pub(crate) fn kill(&self) {
+        if let Some(handle) = &*self.kill.lock().unwrap() {
+            handle.abort();
+        }
+    }
+
+    pub(crate) fn set(&self, exit: ActorExit) {
+        let watchers = {
+            let mut cell = self.exit.lock().unwrap();
+            *cell = Some(exit.clone());
+            std::mem::take(&mut *self.watchers.lock().unwrap())
+        };
+        for watcher in watchers {
+            watcher(&exit);
+        }
+    }
+
+    /// Calls `f` once the actor has exited, right away if it already has.
+    ///
+    /// So `f` may run on the calling thread before `on_exit` returns: don't call it while holding
+    /// a lock that `f` takes.
+    pub(crate) fn on_exit<F>(&self, f: F)
+    where
+        F : FnOnce(&ActorExit) + Send + 'static
+    {
+        let exit = {
+            let cell = self.exit.lock().unwrap();
+            match &*cell {
+                Some(exit) => exit.clone(),
+                None => {
+                    self.watchers.lock().unwrap().push(Box::new(f));
+                    return;
+                }
+            }
+        };
+        f(&exit);
+    }
+
+    /// The error for an `ask` whose reply channel was dropped (or couldn't be sent at all).
+    pub(crate) fn ask_error(&self, e: MailboxError) -> MailboxError {
+        return match self.get() {
+            Some(ActorExit::Panicked(_)) => MailboxError::ActorPanicked,
+            _ => e,
+        };
+    }
+
+    pub(crate) fn get(&self) -> Option<ActorExit> {
+        return self.exit.lock().unwrap().clone();
+    }
+}
@@ -1,76 +1,320 @@
+#![allow(clippy::needless_return)]
+
+use std::cell::Cell;
 use std::future::Future;
+use std::ops::Deref;
+use std::panic::AssertUnwindSafe;
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use async_channel::{bounded, unbounded, Receiver, Sender};
+use futures_util::future::{select, Either};
+use futures_util::{FutureExt, Stream};
+
+mod error;
+pub use error::*;
+
+mod address;
+pub use address::*;
+
+mod context;
+pub use context::*;
+
+mod spawner;
+pub use spawner::*;
+
+mod exit;
+pub use exit::*;
+
+mod supervisor;
+pub use supervisor::*;
+
+mod actor;
+pub use actor::*;
+
+mod oneshot;
+
+mod reply_stream;
+pub use reply_stream::*;
+
+mod overflow;
+pub use overflow::*;
+
+mod priority;
+pub use priority::*;
+
+mod control;
+pub use control::*;
+
+mod monitor;
+pub use monitor::*;
+
+mod registry;
+pub use registry::*;
 
-use async_std::channel::*;
+mod system;
+pub use system::*;
 
-#[cfg(test)] use async_std::task;
+mod timer;
+pub use timer::*;
+
+#[cfg(feature = "futures")]
+mod sink;
+#[cfg(feature = "futures")]
+pub use sink::*;
+
+#[cfg(feature = "derive")]
+pub use mailboxxy_derive::Mailbox;
+
+#[cfg(test)] use std::time::Instant;
 
 
 pub struct ReplyChannel<T> {
-    s: Sender<T>,
+    // dropping it during a panic tells the asker that the actor panicked
+    s: oneshot::Sender<T>,
+    // the type of the message the channel was sent in, for the warning on drop
+    #[cfg(debug_assertions)]
+    message: &'static str,
+}
+
+static WARN_ON_UNANSWERED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // set while mailboxxy itself drops undeliverable messages (or the futures of killed actors),
+    // that's not the actor's fault
+    static DISCARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+// the message types of the `ReplyChannel`s the warning was (or would have been) printed for
+#[cfg(all(test, debug_assertions))]
+static UNANSWERED: std::sync::Mutex<Vec<&'static str>> = std::sync::Mutex::new(Vec::new());
+
+/// Drops `value` without warning about the `ReplyChannel`s inside.
+pub(crate) fn discard<T>(value: T) {
+    let prev = DISCARDING.replace(true);
+    drop(value);
+    DISCARDING.set(prev);
+}
+
+/// In debug builds, a `ReplyChannel` dropped without a reply prints a warning with the message type
+/// to stderr, unless the asker had already given up, the actor panicked or was killed, or mailboxxy
+/// dropped the message itself. Disabled by default, has no effect in release builds.
+pub fn warn_on_unanswered_reply(enabled: bool) {
+    WARN_ON_UNANSWERED.store(enabled, Ordering::Relaxed);
 }
 
 impl<T> ReplyChannel<T> {
-    pub fn reply(&self, value: T) {
-        self.s.send_blocking(value).unwrap();
-        self.s.close();
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    pub(crate) fn new(message: &'static str) -> (Self, oneshot::Receiver<T>) {
+        let (s, r) = oneshot::channel();
+        let rc = ReplyChannel {
+            s,
+            #[cfg(debug_assertions)]
+            message,
+        };
+        return (rc, r);
     }
-}
 
+    /// Hands the value back if the asker has already given up.
+    pub fn reply(mut self, value: T) -> Result<(), T> {
+        return self.s.send(value);
+    }
 
-pub struct MailBox<TMessage, THandle> {
-    sender: Sender<TMessage>,
-    pub handle: THandle
+    /// True once nobody is waiting for the reply anymore (e.g. the `ask` timed out),
+    /// so a slow handler can skip the work.
+    pub fn is_canceled(&self) -> bool {
+        return self.s.is_closed();
+    }
 }
 
+impl<T, E> ReplyChannel<Result<T, E>> {
+    /// `reply(Ok(value))`, for use with `ask_result`.
+    pub fn reply_ok(self, value: T) -> Result<(), Result<T, E>> {
+        return self.reply(Ok(value));
+    }
+
+    /// `reply(Err(error))`, for use with `ask_result`.
+    pub fn reply_err(self, error: E) -> Result<(), Result<T, E>> {
+        return self.reply(Err(error));
+    }
+}
 
-impl<TMessage, THandle> MailBox<TMessage, THandle> {
-    pub fn post(&self, msg: TMessage) {
-        self.sender.send_blocking(msg).unwrap();
+#[cfg(debug_assertions)]
+impl<T> Drop for ReplyChannel<T> {
+    fn drop(&mut self) {
+        // the asker gets `MailboxError::NoReply` (or `ActorPanicked`) either way
+        if self.s.is_done() || self.is_canceled() || std::thread::panicking() || DISCARDING.get() {
+            return;
+        }
+        #[cfg(test)]
+        UNANSWERED.lock().unwrap().push(self.message);
+        if WARN_ON_UNANSWERED.load(Ordering::Relaxed) {
+            eprintln!(
+                "mailboxxy: ReplyChannel<{}> in a {} message was dropped without a reply",
+                std::any::type_name::<T>(),
+                self.message
+            );
+        }
     }
+}
+
+
+/// Resolves to the `ActorExit` once the actor function has returned or panicked.
+pub struct JoinHandle {
+    done: Pin<Box<Receiver<()>>>,
+    exit: Arc<ExitCell>,
+}
 
-    pub async fn ask<TResult>(&self, cb: fn(ReplyChannel<TResult>) -> TMessage) -> TResult{
+impl JoinHandle {
+    pub fn is_finished(&self) -> bool {
+        return self.done.is_closed();
+    }
+}
 
-        let (s,r) = bounded(1);
+impl Future for JoinHandle {
+    type Output = ActorExit;
 
-        let rc = ReplyChannel { s };
-        let msg = cb(rc);
-        self.sender.send_blocking(msg).unwrap();
-        return r.recv().await.unwrap();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<ActorExit> {
+        // nothing is ever sent, the channel closes when the actor future is done
+        if self.done.as_mut().poll_next(cx).is_pending() {
+            return Poll::Pending;
+        }
+        return Poll::Ready(self.exit.get().unwrap_or_else(|| {
+            ActorExit::Error(Arc::from(Box::<dyn std::error::Error + Send + Sync>::from("actor task was dropped before it finished")))
+        }));
     }
 }
 
 
-pub struct MailboxContext<TMessage> {
-    receiver: Receiver<TMessage>
+/// Owns a running actor: its `Address` (which `MailBox` derefs to) and its `JoinHandle`.
+pub struct MailBox<TMessage> {
+    address: Address<TMessage>,
+    pub handle: JoinHandle
 }
 
-impl<TMessage> MailboxContext<TMessage> {
-    pub async fn dequeue(&self) -> TMessage {
-        return self.receiver.recv().await.unwrap();
+
+impl<TMessage> MailBox<TMessage> {
+    pub fn address(&self) -> Address<TMessage> {
+        return self.address.clone();
+    }
+
+    /// Closes the mailbox and waits until the actor has drained it and returned.
+    pub async fn shutdown(self) -> ActorExit {
+        self.address.close();
+        return self.handle.await;
     }
 }
 
-pub enum MailboxBounds {
+impl<TMessage> Deref for MailBox<TMessage> {
+    type Target = Address<TMessage>;
+
+    fn deref(&self) -> &Address<TMessage> {
+        return &self.address;
+    }
+}
+
+
+pub enum MailboxBounds<TMessage> {
     Unbounded,
-    Bounded(usize)
+    /// Posting waits while the mailbox is full.
+    Bounded(usize),
+    /// At most `n` queued messages, the `Overflow` policy decides what happens when it's full.
+    BoundedWith(usize, Overflow<TMessage>),
 }
 
-pub fn start_mailbox<TMessage, F, Fut, THandle>(bounds: MailboxBounds, f: F, spawn: fn(Fut) -> THandle) -> MailBox<TMessage, THandle> 
+/// Both ends of a new mailbox.
+type Channel<TMessage> = (Sender<TMessage>, Receiver<TMessage>, Option<Arc<Overflowing<TMessage>>>);
+
+impl<TMessage> MailboxBounds<TMessage> {
+    pub(crate) fn channel(self) -> Channel<TMessage> {
+        let (n, policy) = match self {
+            MailboxBounds::Unbounded => {
+                let (s, r) = unbounded();
+                return (s, r, None);
+            }
+            MailboxBounds::Bounded(n) => (n, Overflow::Block),
+            MailboxBounds::BoundedWith(n, policy) => (n, policy),
+        };
+        let (s, r) = bounded(n);
+        let overflow = match policy {
+            Overflow::Block => None,
+            policy => Some(Arc::new(Overflowing::new(policy, r.clone()))),
+        };
+        return (s, r, overflow);
+    }
+}
+
+/// Spawns the actor function `f`, which may return `()` or a `Result<(), E>`.
+///
+/// If `f` panics, the panic is caught and reported through the `JoinHandle`.
+pub fn start_mailbox<TMessage, F, Fut, S>(bounds: MailboxBounds<TMessage>, f: F, spawner: S) -> MailBox<TMessage>
 where
+    TMessage : Send + 'static,
     F : FnOnce(MailboxContext<TMessage>) -> Fut,
-    Fut : Future<Output = ()>,
-    THandle : Future<Output = ()>
+    Fut : Future + Send + 'static,
+    Fut::Output : ActorResult,
+    S : Spawner
 {
-    let (s,r) = match bounds {
-        MailboxBounds::Unbounded => unbounded(),
-        MailboxBounds::Bounded(n) => bounded(n)
-    };
+    return start_mailbox_with(bounds, None, f, spawner);
+}
 
-    let ctx = MailboxContext { receiver: r };
+pub(crate) fn start_mailbox_with<TMessage, F, Fut, S>(bounds: MailboxBounds<TMessage>, priority: Option<PriorityFn<TMessage>>, f: F, spawner: S) -> MailBox<TMessage>
+where
+    TMessage : Send + 'static,
+    F : FnOnce(MailboxContext<TMessage>) -> Fut,
+    Fut : Future + Send + 'static,
+    Fut::Output : ActorResult,
+    S : Spawner
+{
+    let (s,r,overflow) = bounds.channel();
+    let (control_s, control_r) = unbounded();
+    let exit = Arc::new(ExitCell::default());
 
-    let handle = spawn(f(ctx));
+    let address = Address::new(s, control_s, exit.clone(), overflow);
 
-    return MailBox { sender: s, handle };
+    let inbox = Inbox::new(r, control_r, address.downgrade(), priority);
+    let ctx = MailboxContext::new(inbox.clone());
+
+    let handle = spawn_tracked(&spawner, exit, f(ctx), move || inbox.close_and_drain());
+
+    return MailBox { address, handle };
+}
+
+/// Runs `fut` with panics caught and killable through `exit`, records how it exited in `exit`,
+/// then runs `cleanup`.
+pub(crate) fn spawn_tracked<S, Fut, C>(spawner: &S, exit: Arc<ExitCell>, fut: Fut, cleanup: C) -> JoinHandle
+where
+    S : Spawner + ?Sized,
+    Fut : Future + Send + 'static,
+    Fut::Output : ActorResult,
+    C : FnOnce() + Send + 'static
+{
+    let (done_s, done_r) = bounded::<()>(1);
+    let exit_ = exit.clone();
+    let fut = exit.killable(fut);
+    spawner.spawn(Box::pin(async move {
+        let result = match AssertUnwindSafe(fut).catch_unwind().await {
+            Ok(result) => result,
+            Err(payload) => ActorExit::from_panic(payload),
+        };
+        exit_.set(result);
+        cleanup();
+        drop(done_s);
+    }));
+
+    return JoinHandle { done: Box::pin(done_r), exit };
+}
+
+/// `None` if `fut` didn't finish within `timeout`.
+pub(crate) async fn timeout<Fut: Future>(timeout: Duration, fut: Fut) -> Option<Fut::Output> {
+    let fut = pin!(fut);
+    return match select(fut, futures_timer::Delay::new(timeout)).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    };
 }
 
 
@@ -78,7 +322,8 @@ where
 enum TestMsg {
     Increment,
     Decrement,
-    GetValue(ReplyChannel<i32>)
+    GetValue(ReplyChannel<i32>),
+    Compare(i32, ReplyChannel<bool>)
 }
 
 #[cfg(test)]
@@ -87,13 +332,13 @@ async fn mailbox_fn(ctx:MailboxContext<TestMsg>) {
     // local state
     let mut count = 0;
 
-    loop {
-        let msg: TestMsg = ctx.dequeue().await;
+    while let Some(msg) = ctx.dequeue().await {
 
         match msg {
-            TestMsg::Increment => count = count + 1,
-            TestMsg::Decrement => count = count - 1,
-            TestMsg::GetValue(rc) => rc.reply(count)
+            TestMsg::Increment => count += 1,
+            TestMsg::Decrement => count -= 1,
+            TestMsg::GetValue(rc) => { let _ = rc.reply(count); }
+            TestMsg::Compare(n, rc) => { let _ = rc.reply(count == n); }
         }
     }
 }
@@ -101,21 +346,301 @@ async fn mailbox_fn(ctx:MailboxContext<TestMsg>) {
 #[cfg(test)]
 async fn test_async() {
 
-    let mb = start_mailbox(MailboxBounds::Unbounded, mailbox_fn, task::spawn);
+    let mb = start_mailbox(MailboxBounds::Unbounded, mailbox_fn, TestSpawner);
 
-    mb.post(TestMsg::Increment);
-    mb.post(TestMsg::Increment);
-    mb.post(TestMsg::Increment);
-    let val = mb.ask(|rc| TestMsg::GetValue(rc)).await;
+    mb.post(TestMsg::Increment).unwrap();
+    mb.post(TestMsg::Increment).unwrap();
+    mb.post(TestMsg::Increment).unwrap();
+    let val = mb.ask(TestMsg::GetValue).await.unwrap();
     assert_eq!(val, 3);
 
-    mb.post(TestMsg::Decrement);
-    let val = mb.ask(|rc| TestMsg::GetValue(rc)).await;
+    mb.post(TestMsg::Decrement).unwrap();
+    let val = mb.ask(TestMsg::GetValue).await.unwrap();
     assert_eq!(val, 2);
+
+    let expected = 2;
+    let same = mb.ask(|rc| TestMsg::Compare(expected, rc)).await.unwrap();
+    assert!(same);
 }
 
 
 #[test]
 fn test() {
     smol::block_on(test_async());
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+pub(crate) struct TestSpawner;
+
+#[cfg(test)]
+impl Spawner for TestSpawner {
+    fn spawn(&self, fut: BoxFuture) {
+        smol::spawn(fut).detach();
+    }
+}
+
+/// runs the actor to completion before `start_mailbox` returns
+#[cfg(test)]
+struct InlineSpawner;
+
+#[cfg(test)]
+impl Spawner for InlineSpawner {
+    fn spawn(&self, fut: BoxFuture) {
+        smol::block_on(fut);
+    }
+}
+
+#[test]
+fn test_errors() {
+    smol::block_on(async {
+        // the actor exits immediately, dropping its end of the mailbox
+        let mb = start_mailbox(MailboxBounds::Unbounded, |_ctx: MailboxContext<TestMsg>| async {}, InlineSpawner);
+        assert_eq!(mb.post(TestMsg::Increment), Err(MailboxError::Closed));
+        assert_eq!(mb.ask(TestMsg::GetValue).await.err(), Some(MailboxError::Closed));
+
+        // the actor never dequeues, so the second message doesn't fit
+        let mb = start_mailbox(MailboxBounds::Bounded(1), |ctx: MailboxContext<TestMsg>| async move {
+            std::future::pending::<()>().await;
+            drop(ctx);
+        }, TestSpawner);
+        assert!(mb.try_post(TestMsg::Increment).is_ok());
+        assert!(matches!(mb.try_post(TestMsg::Decrement), Err(TryPostError::Full(TestMsg::Decrement))));
+
+        // the actor drops the reply channel without answering
+        let mb = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<TestMsg>| async move {
+            drop(ctx.dequeue().await);
+        }, TestSpawner);
+        assert_eq!(mb.ask(TestMsg::GetValue).await.err(), Some(MailboxError::NoReply));
+    });
+}
+
+#[test]
+fn test_backpressure() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Bounded(1), mailbox_fn, TestSpawner);
+
+        for _ in 0..10 {
+            mb.post_async(TestMsg::Increment).await.unwrap();
+        }
+        assert_eq!(mb.ask(TestMsg::GetValue).await, Ok(10));
+    });
+}
+
+#[test]
+fn test_timeout() {
+    smol::block_on(async {
+        // holds on to the first reply channel, then reports whether its asker gave up
+        let mb = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<TestMsg>| async move {
+            let first = ctx.dequeue().await;
+            let second = ctx.dequeue().await;
+            if let (Some(TestMsg::GetValue(first)), Some(TestMsg::Compare(_, second))) = (first, second) {
+                let canceled = first.is_canceled();
+                assert_eq!(first.reply(1), Err(1));
+                second.reply(canceled).unwrap();
+            }
+        }, TestSpawner);
+
+        let val = mb.ask_timeout(Duration::from_millis(10), TestMsg::GetValue).await;
+        assert_eq!(val, Err(MailboxError::Timeout));
+
+        let canceled = mb.ask_deadline(Instant::now() + Duration::from_secs(5), |rc| TestMsg::Compare(0, rc)).await;
+        assert_eq!(canceled, Ok(true));
+    });
+}
+
+#[test]
+fn test_shutdown() {
+    smol::block_on(async {
+        let count = std::sync::Arc::new(std::sync::atomic::AtomicI32::new(0));
+        let count_ = count.clone();
+        let mb = start_mailbox(MailboxBounds::Unbounded, move |ctx: MailboxContext<TestMsg>| async move {
+            while let Some(msg) = ctx.dequeue().await {
+                if let TestMsg::Increment = msg {
+                    count_.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }
+            }
+        }, TestSpawner);
+
+        mb.post(TestMsg::Increment).unwrap();
+        mb.post(TestMsg::Increment).unwrap();
+        mb.close();
+        assert!(mb.is_closed());
+        assert_eq!(mb.post(TestMsg::Increment), Err(MailboxError::Closed));
+
+        // everything posted before close is still handled
+        mb.shutdown().await;
+        assert_eq!(count.load(std::sync::atomic::Ordering::SeqCst), 2);
+    });
+}
+
+#[test]
+fn test_address() {
+    smol::block_on(async {
+        let MailBox { address, handle } = start_mailbox(MailboxBounds::Unbounded, mailbox_fn, TestSpawner);
+
+        let other = address.clone();
+        let weak = address.downgrade();
+        address.post(TestMsg::Increment).unwrap();
+        other.post(TestMsg::Increment).unwrap();
+        assert_eq!(weak.upgrade().unwrap().ask(TestMsg::GetValue).await, Ok(2));
+
+        // a weak address alone doesn't keep the actor running
+        drop(address);
+        drop(other);
+        handle.await;
+        assert!(weak.upgrade().is_none());
+    });
+}
+
+#[test]
+fn test_panic() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<TestMsg>| async move {
+            while let Some(msg) = ctx.dequeue().await {
+                if let TestMsg::GetValue(_rc) = msg {
+                    panic!("boom");
+                }
+            }
+        }, TestSpawner);
+
+        // the second ask is still queued when the actor panics
+        let (first, second) = futures_util::future::join(
+            mb.ask(TestMsg::GetValue),
+            mb.ask(|rc| TestMsg::Compare(0, rc))
+        ).await;
+        assert_eq!(first, Err(MailboxError::ActorPanicked));
+        assert_eq!(second, Err(MailboxError::ActorPanicked));
+
+        match mb.shutdown().await {
+            ActorExit::Panicked(msg) => assert_eq!(msg, "boom"),
+            other => panic!("unexpected exit: {}", other),
+        }
+
+        let mb = start_mailbox(MailboxBounds::Unbounded, |_ctx: MailboxContext<TestMsg>| async {
+            return Err("nope");
+        }, TestSpawner);
+        assert_eq!(mb.shutdown().await.to_string(), "failed: nope");
+    });
+}
+
+#[test]
+fn test_ask_result() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<(i32, ReplyChannel<Result<i32, String>>)>| async move {
+            while let Some((n, rc)) = ctx.dequeue().await {
+                let _ = match n {
+                    0 => rc.reply_err("division by zero".to_string()),
+                    n => rc.reply_ok(100 / n),
+                };
+            }
+        }, TestSpawner);
+
+        assert_eq!(mb.ask_result(|rc| (4, rc)).await, Ok(25));
+        assert_eq!(mb.ask_result(|rc| (0, rc)).await, Err(AskError::Actor("division by zero".to_string())));
+
+        mb.close();
+        assert_eq!(mb.ask_result(|rc| (1, rc)).await, Err(AskError::Mailbox(MailboxError::Closed)));
+    });
+}
+
+#[cfg(all(test, debug_assertions))]
+enum WarnMsg {
+    Forget(ReplyChannel<i32>),
+    Hold(ReplyChannel<i32>),
+    Held(ReplyChannel<usize>),
+    Fail,
+}
+
+/// Forgets to answer `Forget`, and holds on to the `ReplyChannel`s of `Hold` until it exits.
+#[cfg(all(test, debug_assertions))]
+async fn forgetful(ctx: MailboxContext<WarnMsg>) -> Result<(), &'static str> {
+    let mut held = Vec::new();
+    while let Some(msg) = ctx.dequeue().await {
+        match msg {
+            WarnMsg::Forget(_rc) => {}
+            WarnMsg::Hold(rc) => held.push(rc),
+            WarnMsg::Held(rc) => { let _ = rc.reply(held.len()); }
+            WarnMsg::Fail => return Err("failed"),
+        }
+    }
+    return Ok(());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_unanswered_warning() {
+    let warnings = || UNANSWERED.lock().unwrap().iter().filter(|m| **m == std::any::type_name::<WarnMsg>()).count();
+
+    smol::block_on(async {
+        // the actor's fault
+        let mb = start_mailbox(MailboxBounds::Unbounded, forgetful, TestSpawner);
+        assert_eq!(mb.ask(WarnMsg::Forget).await, Err(MailboxError::NoReply));
+        assert_eq!(warnings(), 1);
+
+        // killed while holding one
+        let (held, ()) = futures_util::future::join(mb.ask(WarnMsg::Hold), async {
+            assert_eq!(mb.ask(WarnMsg::Held).await, Ok(1));
+            mb.kill();
+        }).await;
+        assert_eq!(held, Err(MailboxError::NoReply));
+
+        // stopped by the supervisor because a sibling failed
+        let mut sup = Supervisor::new(RestartStrategy::OneForAll);
+        let holding = sup.add_child(MailboxBounds::Unbounded, forgetful);
+        let failing = sup.add_child(MailboxBounds::Unbounded, forgetful);
+        let _handle = sup.start(TestSpawner);
+        let (held, ()) = futures_util::future::join(holding.ask(WarnMsg::Hold), async {
+            assert_eq!(holding.ask(WarnMsg::Held).await, Ok(1));
+            failing.post(WarnMsg::Fail).unwrap();
+        }).await;
+        assert_eq!(held, Err(MailboxError::NoReply));
+
+        // still queued when the actor exits, and posted after that
+        let mb = start_mailbox(MailboxBounds::Unbounded, forgetful, TestSpawner);
+        mb.post(WarnMsg::Fail).unwrap();
+        assert!(mb.ask(WarnMsg::Forget).await.is_err());
+        assert_eq!(mb.ask(WarnMsg::Forget).await, Err(MailboxError::Closed));
+
+        assert_eq!(warnings(), 1);
+    });
+}
+
+/// `#[derive(Mailbox)]` rejects variants whose method would be hidden by a method of `Address` or `MailBox`,
+/// so every one of them has to be in the `RESERVED` list of mailboxxy-derive.
+#[test]
+fn test_derive_reserved_names() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let derive = std::fs::read_to_string(dir.join("mailboxxy-derive/src/lib.rs")).unwrap();
+    let list = &derive[derive.find("const RESERVED").unwrap()..];
+    let reserved: Vec<&str> = list[..list.find("];").unwrap()].split('"').skip(1).step_by(2).collect();
+
+    let mut missing = Vec::new();
+    for file in std::fs::read_dir(dir.join("src")).unwrap() {
+        let source = std::fs::read_to_string(file.unwrap().path()).unwrap();
+        // `Some(is_trait_impl)` inside an impl block of `Address` or `MailBox`, but not `WeakAddress`
+        let mut inside = None;
+        for line in source.lines() {
+            if line.starts_with("impl") {
+                inside = (line.contains(" Address<") || line.contains(" MailBox<")).then(|| line.contains(" for "));
+                continue;
+            }
+            if line.starts_with('}') {
+                inside = None;
+            }
+            let (Some(is_trait_impl), Some(signature)) = (inside, line.strip_prefix("    ")) else { continue };
+            // every method of a trait impl, only the public ones of the others
+            let signature = match signature.strip_prefix("pub ") {
+                Some(signature) => signature,
+                None if is_trait_impl => signature,
+                None => continue,
+            };
+            let Some(name) = signature.trim_start_matches("async ").strip_prefix("fn ") else { continue };
+            let name: String = name.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+            if !reserved.contains(&name.as_str()) {
+                missing.push(name);
+            }
+        }
+    }
+    assert!(missing.is_empty(), "missing in mailboxxy-derive's RESERVED: {:?}", missing);
+}
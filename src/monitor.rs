@@ -0,0 +1,197 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::{ActorExit, Address, MailboxContext};
+
+
+/// Identifies an actor (more precisely its mailbox) for as long as the process runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ActorId(u64);
+
+impl ActorId {
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        return ActorId(NEXT.fetch_add(1, Ordering::Relaxed));
+    }
+}
+
+impl fmt::Display for ActorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+
+/// Delivered to a monitoring actor once the monitored actor has exited, see `MailboxContext::monitor`.
+#[derive(Debug, Clone)]
+pub struct Down {
+    pub id: ActorId,
+    pub reason: ActorExit,
+}
+
+
+impl<TMessage> Address<TMessage> {
+    pub fn id(&self) -> ActorId {
+        return self.exit.id;
+    }
+}
+
+impl<TMessage> MailboxContext<TMessage> {
+    /// The id of this actor, the same as `Address::id`.
+    pub fn id(&self) -> ActorId {
+        return self.inbox().address.exit.id;
+    }
+
+    /// Delivers a `Down` into this actor's mailbox once the actor behind `address` has exited,
+    /// however that happened. If it already has, the `Down` is delivered right away.
+    ///
+    /// `Down`s are delivered ahead of the queued messages and aren't subject to the mailbox bounds.
+    /// The monitor doesn't keep either actor alive.
+    pub fn monitor<T>(&self, address: &Address<T>)
+    where
+        TMessage : From<Down> + Send + 'static
+    {
+        let inbox = Arc::downgrade(self.inbox());
+        let id = address.id();
+        address.exit.on_exit(move |reason| {
+            if let Some(inbox) = inbox.upgrade() {
+                inbox.notify(TMessage::from(Down { id, reason: reason.clone() }));
+            }
+        });
+    }
+
+    /// Links this actor with the actor behind `address`: if either of them exits with anything but
+    /// `ActorExit::Normal`, the other one is killed, see `Address::kill`.
+    ///
+    /// A supervised actor killed this way is restarted like after any other failure.
+    pub fn link<T>(&self, address: &Address<T>) {
+        let this = &self.inbox().address.exit;
+        let other = &address.exit;
+
+        let other_ = Arc::downgrade(other);
+        this.on_exit(move |reason| {
+            if let (false, Some(other)) = (reason.is_normal(), other_.upgrade()) {
+                other.kill();
+            }
+        });
+        let this_ = Arc::downgrade(this);
+        other.on_exit(move |reason| {
+            if let (false, Some(this)) = (reason.is_normal(), this_.upgrade()) {
+                this.kill();
+            }
+        });
+    }
+}
+
+
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::{start_mailbox, MailboxBounds, TestSpawner};
+
+#[cfg(test)]
+enum Msg {
+    Down(Down),
+    Crash,
+}
+
+#[cfg(test)]
+impl From<Down> for Msg {
+    fn from(down: Down) -> Self {
+        return Msg::Down(down);
+    }
+}
+
+/// Handles `Msg::Crash` by panicking.
+#[cfg(test)]
+async fn fragile(ctx: MailboxContext<Msg>) {
+    while let Some(msg) = ctx.dequeue().await {
+        if let Msg::Crash = msg {
+            panic!("crashed");
+        }
+    }
+}
+
+#[test]
+fn test_monitor() {
+    smol::block_on(async {
+        let target = start_mailbox(MailboxBounds::Unbounded, fragile, TestSpawner);
+        let target_address = target.address();
+
+        let (downs_s, downs_r) = async_channel::unbounded();
+        let watcher = start_mailbox(MailboxBounds::Unbounded, move |ctx: MailboxContext<Msg>| async move {
+            ctx.monitor(&target_address);
+            while let Some(msg) = ctx.dequeue().await {
+                if let Msg::Down(down) = msg {
+                    let _ = downs_s.send(down).await;
+                }
+            }
+        }, TestSpawner);
+
+        target.post(Msg::Crash).unwrap();
+        let down = downs_r.recv().await.unwrap();
+        assert_eq!(down.id, target.id());
+        assert!(down.reason.is_panicked());
+
+        // the watcher is still fine
+        assert_eq!(watcher.ping().await, Ok(()));
+    });
+}
+
+#[test]
+fn test_scan_for_down() {
+    smol::block_on(async {
+        let target = start_mailbox(MailboxBounds::Unbounded, fragile, TestSpawner);
+        let target_address = target.address();
+
+        let (down_s, down_r) = async_channel::bounded(1);
+        let watcher = start_mailbox(MailboxBounds::Unbounded, move |ctx: MailboxContext<Msg>| async move {
+            ctx.monitor(&target_address);
+            if let Some(Msg::Down(down)) = ctx.scan(|msg| matches!(msg, Msg::Down(_))).await {
+                let _ = down_s.send(down).await;
+            }
+        }, TestSpawner);
+
+        // answered from inside `scan`, so the `Down` has to wake it up
+        assert_eq!(watcher.ping().await, Ok(()));
+        target.post(Msg::Crash).unwrap();
+        let down = crate::timeout(Duration::from_secs(5), down_r.recv()).await.unwrap().unwrap();
+        assert_eq!(down.id, target.id());
+    });
+}
+
+#[test]
+fn test_link() {
+    smol::block_on(async {
+        let a = start_mailbox(MailboxBounds::Unbounded, fragile, TestSpawner);
+        let b = start_mailbox(MailboxBounds::Unbounded, fragile, TestSpawner);
+        let c = start_mailbox(MailboxBounds::Unbounded, fragile, TestSpawner);
+
+        let (a_address, c_address) = (a.address(), c.address());
+        let linker = start_mailbox(MailboxBounds::Unbounded, move |ctx: MailboxContext<Msg>| async move {
+            ctx.link(&a_address);
+            ctx.link(&c_address);
+            fragile(ctx).await;
+        }, TestSpawner);
+        assert_eq!(linker.ping().await, Ok(()));
+
+        // `a` crashing takes down the linker, which takes down `c`, but `b` isn't linked
+        a.post(Msg::Crash).unwrap();
+        assert!(matches!(linker.handle.await, crate::ActorExit::Killed));
+        assert!(matches!(crate::timeout(Duration::from_secs(5), c.handle).await, Some(crate::ActorExit::Killed)));
+        assert!(b.exit().is_none());
+
+        // a normal exit doesn't propagate
+        let d = start_mailbox(MailboxBounds::Unbounded, fragile, TestSpawner);
+        let d_address = d.address();
+        let linker = start_mailbox(MailboxBounds::Unbounded, move |ctx: MailboxContext<Msg>| async move {
+            ctx.link(&d_address);
+            fragile(ctx).await;
+        }, TestSpawner);
+        assert_eq!(linker.ping().await, Ok(()));
+        assert!(d.shutdown().await.is_normal());
+        assert_eq!(linker.ping().await, Ok(()));
+    });
+}
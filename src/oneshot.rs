@@ -0,0 +1,153 @@
+use std::cell::UnsafeCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_util::task::AtomicWaker;
+
+use crate::MailboxError;
+
+
+// state bits
+const VALUE: u8 = 1;
+const SENDER_DONE: u8 = 2;
+const PANICKED: u8 = 4;
+const RECEIVER_GONE: u8 = 8;
+
+/// A single-use channel for replies, one allocation shared by both ends.
+///
+/// The sender writes the value before setting `VALUE | SENDER_DONE`, the receiver only reads it
+/// after seeing `VALUE`, and the sender only takes it back after seeing `RECEIVER_GONE`.
+struct Inner<T> {
+    state: AtomicU8,
+    value: UnsafeCell<Option<T>>,
+    waker: AtomicWaker,
+}
+
+// the value is only ever accessed by one side at a time, see above
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+pub(crate) fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Inner {
+        state: AtomicU8::new(0),
+        value: UnsafeCell::new(None),
+        waker: AtomicWaker::new(),
+    });
+    return (Sender { inner: inner.clone(), done: false }, Receiver { inner });
+}
+
+
+pub(crate) struct Sender<T> {
+    inner: Arc<Inner<T>>,
+    done: bool,
+}
+
+impl<T> Sender<T> {
+    /// Hands the value back if the receiver is already gone. Must only be called once.
+    pub(crate) fn send(&mut self, value: T) -> Result<(), T> {
+        debug_assert!(!self.done, "oneshot::Sender::send called twice");
+        self.done = true;
+        if self.is_closed() {
+            return Err(value);
+        }
+        unsafe { *self.inner.value.get() = Some(value) };
+        let prev = self.inner.state.fetch_or(VALUE | SENDER_DONE, Ordering::AcqRel);
+        if prev & RECEIVER_GONE != 0 {
+            // the receiver went away in the meantime and won't look at the value anymore
+            return Err(unsafe { (*self.inner.value.get()).take() }.unwrap());
+        }
+        self.inner.waker.wake();
+        return Ok(());
+    }
+
+    /// True once `send` was called.
+    #[cfg(debug_assertions)]
+    pub(crate) fn is_done(&self) -> bool {
+        return self.done;
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        return self.inner.state.load(Ordering::Acquire) & RECEIVER_GONE != 0;
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        // tells the receiver that the actor panicked while holding the channel
+        let panicked = if std::thread::panicking() { PANICKED } else { 0 };
+        self.inner.state.fetch_or(SENDER_DONE | panicked, Ordering::AcqRel);
+        self.inner.waker.wake();
+    }
+}
+
+
+/// Resolves to the value, `MailboxError::NoReply` if the sender was dropped without sending,
+/// or `MailboxError::ActorPanicked` if it was dropped during a panic.
+pub(crate) struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, MailboxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.inner.state.load(Ordering::Acquire);
+        if state & SENDER_DONE == 0 {
+            self.inner.waker.register(cx.waker());
+            // the sender may have finished before the waker was registered
+            state = self.inner.state.load(Ordering::Acquire);
+            if state & SENDER_DONE == 0 {
+                return Poll::Pending;
+            }
+        }
+
+        if state & VALUE != 0 {
+            if let Some(value) = unsafe { (*self.inner.value.get()).take() } {
+                return Poll::Ready(Ok(value));
+            }
+        }
+        if state & PANICKED != 0 {
+            return Poll::Ready(Err(MailboxError::ActorPanicked));
+        }
+        return Poll::Ready(Err(MailboxError::NoReply));
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.inner.state.fetch_or(RECEIVER_GONE, Ordering::AcqRel);
+    }
+}
+
+
+#[test]
+fn test_oneshot() {
+    smol::block_on(async {
+        let (mut s, r) = channel();
+        assert!(!s.is_closed());
+        assert_eq!(s.send(1), Ok(()));
+        assert_eq!(r.await, Ok(1));
+
+        let (s, r) = channel::<i32>();
+        drop(s);
+        assert_eq!(r.await, Err(MailboxError::NoReply));
+
+        let (mut s, r) = channel();
+        drop(r);
+        assert!(s.is_closed());
+        assert_eq!(s.send(1), Err(1));
+
+        // the value arrives while the receiver is waiting on another thread
+        let (mut s, r) = channel();
+        let t = std::thread::spawn(move || smol::block_on(r));
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        s.send(vec![1, 2]).unwrap();
+        assert_eq!(t.join().unwrap(), Ok(vec![1, 2]));
+    });
+}
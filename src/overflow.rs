@@ -0,0 +1,203 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use async_channel::{Receiver, Sender, TrySendError};
+
+use crate::TryPostError;
+
+
+/// Decides whether two messages have the same key, see `Overflow::coalesce`.
+pub type SameKey<TMessage> = Box<dyn Fn(&TMessage, &TMessage) -> bool + Send + Sync>;
+
+/// What happens to a message posted to a full `MailboxBounds::BoundedWith` mailbox.
+///
+/// Except for `Block`, posting never waits, so a lagging actor sheds load instead of stalling
+/// its producers. Messages dropped this way are counted by `Address::dropped`; an `ask` whose
+/// message was dropped fails with `MailboxError::NoReply`.
+pub enum Overflow<TMessage> {
+    /// Wait for room, like `MailboxBounds::Bounded`.
+    Block,
+    /// Drop the message being posted.
+    DropNewest,
+    /// Drop the oldest queued message to make room.
+    DropOldest,
+    /// Fail with `MailboxError::Full` (`TryPostError::Full` from `try_post`).
+    Reject,
+    /// Replace the queued message with the same key, keeping its place in the queue.
+    /// If there is none, drop the oldest queued message if the mailbox is still full.
+    /// Use `Overflow::coalesce` to build it.
+    Coalesce(SameKey<TMessage>),
+}
+
+impl<TMessage> Overflow<TMessage> {
+    /// E.g. `Overflow::coalesce(|m: &Telemetry| m.sensor_id)` keeps only the latest reading per sensor
+    /// once the mailbox is full.
+    ///
+    /// `key` runs on whichever thread posts to the full mailbox, which for `post_after` and `post_interval`
+    /// is the timer thread all timers share, so it should be cheap and must not block.
+    pub fn coalesce<K, F>(key: F) -> Self
+    where
+        K : PartialEq,
+        F : Fn(&TMessage) -> K + Send + Sync + 'static
+    {
+        return Overflow::Coalesce(Box::new(move |a, b| key(a) == key(b)));
+    }
+}
+
+
+/// Shared by all `Address`es of a mailbox with an overflow policy other than `Block`.
+pub(crate) struct Overflowing<TMessage> {
+    policy: Overflow<TMessage>,
+    // to take queued messages out again for `DropOldest` and `Coalesce`
+    receiver: Receiver<TMessage>,
+    // `DropOldest` and `Coalesce` rearrange the queue, nobody else may post meanwhile
+    lock: Mutex<()>,
+    dropped: AtomicU64,
+}
+
+impl<TMessage> Overflowing<TMessage> {
+    pub(crate) fn new(policy: Overflow<TMessage>, receiver: Receiver<TMessage>) -> Self {
+        return Overflowing { policy, receiver, lock: Mutex::new(()), dropped: AtomicU64::new(0) };
+    }
+
+    pub(crate) fn dropped(&self) -> u64 {
+        return self.dropped.load(Ordering::Relaxed);
+    }
+
+    fn drop_one(&self, msg: TMessage) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        crate::discard(msg);
+    }
+
+    /// Never waits, only fails if the mailbox is closed, or full with `Overflow::Reject`.
+    pub(crate) fn try_post(&self, sender: &Sender<TMessage>, msg: TMessage) -> Result<(), TryPostError<TMessage>> {
+        let _lock = match self.policy {
+            Overflow::DropOldest | Overflow::Coalesce(_) => Some(self.lock.lock().unwrap()),
+            _ => None,
+        };
+
+        let msg = match sender.try_send(msg) {
+            Ok(()) => return Ok(()),
+            Err(TrySendError::Closed(msg)) => return Err(TryPostError::Closed(msg)),
+            Err(TrySendError::Full(msg)) => msg,
+        };
+
+        match &self.policy {
+            // not used for `Block`, that's a plain bounded channel
+            Overflow::Block | Overflow::Reject => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return Err(TryPostError::Full(msg));
+            }
+            Overflow::DropNewest => {
+                self.drop_one(msg);
+                return Ok(());
+            }
+            Overflow::DropOldest => {
+                let mut msg = msg;
+                loop {
+                    if let Ok(oldest) = self.receiver.try_recv() {
+                        self.drop_one(oldest);
+                    }
+                    msg = match sender.try_send(msg) {
+                        Ok(()) => return Ok(()),
+                        Err(TrySendError::Closed(msg)) => return Err(TryPostError::Closed(msg)),
+                        Err(TrySendError::Full(msg)) => msg,
+                    };
+                }
+            }
+            Overflow::Coalesce(same_key) => {
+                self.coalesce(sender, same_key, msg);
+                return Ok(());
+            }
+        }
+    }
+
+    fn coalesce(&self, sender: &Sender<TMessage>, same_key: &SameKey<TMessage>, msg: TMessage) {
+        // the actor keeps receiving meanwhile, but only ever sees the queue in order
+        let mut queued = Vec::new();
+        while let Ok(queued_msg) = self.receiver.try_recv() {
+            queued.push(queued_msg);
+        }
+        if let Some(slot) = queued.iter_mut().find(|queued_msg| same_key(queued_msg, &msg)) {
+            let replaced = std::mem::replace(slot, msg);
+            self.drop_one(replaced);
+        } else {
+            // the actor may have made room since the mailbox was found full
+            if queued.len() >= sender.capacity().unwrap_or(usize::MAX) {
+                let oldest = queued.remove(0);
+                self.drop_one(oldest);
+            }
+            queued.push(msg);
+        }
+        // there is room for all of them, the lock keeps other producers out
+        for queued_msg in queued {
+            if let Err(e) = sender.try_send(queued_msg) {
+                crate::discard(e.into_inner());
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+use crate::{start_mailbox, MailboxBounds, MailboxContext, MailboxError, TestSpawner};
+
+/// Posts `msgs` while the actor isn't dequeuing yet, then returns what the actor received,
+/// the result of every post and the number of dropped messages.
+#[cfg(test)]
+fn run(policy: Overflow<i32>, msgs: &[i32]) -> (Vec<i32>, Vec<Result<(), MailboxError>>, u64) {
+    smol::block_on(async {
+        let (go_s, go_r) = async_channel::bounded::<()>(1);
+        let (seen_s, seen_r) = async_channel::bounded(1);
+        let mb = start_mailbox(MailboxBounds::BoundedWith(3, policy), move |ctx: MailboxContext<i32>| async move {
+            let _ = go_r.recv().await;
+            let mut seen = Vec::new();
+            while let Some(n) = ctx.dequeue().await {
+                seen.push(n);
+            }
+            let _ = seen_s.send(seen).await;
+        }, TestSpawner);
+
+        let results = msgs.iter().map(|&n| mb.post(n)).collect();
+        let dropped = mb.dropped();
+
+        mb.close();
+        go_s.send(()).await.unwrap();
+        assert!(mb.shutdown().await.is_normal());
+        return (seen_r.recv().await.unwrap(), results, dropped);
+    })
+}
+
+#[test]
+fn test_overflow() {
+    let (seen, results, dropped) = run(Overflow::DropNewest, &[1, 2, 3, 4, 5]);
+    assert_eq!((seen, dropped), (vec![1, 2, 3], 2));
+    assert!(results.iter().all(|r| r.is_ok()));
+
+    let (seen, _, dropped) = run(Overflow::DropOldest, &[1, 2, 3, 4, 5]);
+    assert_eq!((seen, dropped), (vec![3, 4, 5], 2));
+
+    let (seen, results, dropped) = run(Overflow::Reject, &[1, 2, 3, 4, 5]);
+    assert_eq!((seen, dropped), (vec![1, 2, 3], 2));
+    assert_eq!(results[3..], [Err(MailboxError::Full), Err(MailboxError::Full)]);
+
+    // 11 replaces 1 in place, 4 has no match and pushes out the oldest
+    let (seen, _, dropped) = run(Overflow::coalesce(|n: &i32| n % 10), &[1, 2, 3, 11, 4]);
+    assert_eq!((seen, dropped), (vec![2, 3, 4], 2));
+    let (seen, _, dropped) = run(Overflow::coalesce(|n: &i32| n % 10), &[1, 2, 3, 12]);
+    assert_eq!((seen, dropped), (vec![1, 12, 3], 1));
+}
+
+#[test]
+fn test_coalesce_with_room() {
+    // the mailbox was full when the post was tried, but the actor has taken a message since
+    let (s, r) = async_channel::bounded(3);
+    let overflowing = Overflowing::new(Overflow::coalesce(|n: &i32| n % 10), r.clone());
+    s.try_send(1).unwrap();
+    s.try_send(2).unwrap();
+    let Overflow::Coalesce(same_key) = &overflowing.policy else { unreachable!() };
+    overflowing.coalesce(&s, same_key, 4);
+
+    assert_eq!(overflowing.dropped(), 0);
+    assert_eq!(std::iter::from_fn(|| r.try_recv().ok()).collect::<Vec<_>>(), vec![1, 2, 4]);
+}
@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+use std::future::Future;
+
+use crate::{start_mailbox_with, ActorResult, MailBox, MailboxBounds, MailboxContext, Spawner};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+/// Implemented by message enums for `start_prioritized_mailbox`.
+pub trait Prioritized {
+    fn priority(&self) -> Priority;
+}
+
+/// After this many messages in a row were served ahead of a waiting lower-priority message,
+/// that message is served next.
+pub(crate) const STARVATION_LIMIT: u32 = 16;
+
+
+/// Messages already taken out of the channel, but not yet dequeued by the actor.
+///
+/// Without a priority function, everything goes into the `Normal` lane.
+pub(crate) struct Lanes<TMessage> {
+    lanes: [VecDeque<TMessage>; 3],
+    // how often each lane was non-empty while a higher lane was served
+    passed_over: [u32; 3],
+}
+
+impl<TMessage> Lanes<TMessage> {
+    pub(crate) fn new() -> Self {
+        return Lanes { lanes: Default::default(), passed_over: [0; 3] };
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        return self.lanes.iter().map(|lane| lane.len()).sum();
+    }
+
+    pub(crate) fn push(&mut self, priority: Priority, msg: TMessage) {
+        self.lanes[priority as usize].push_back(msg);
+    }
+
+    /// The oldest message of the highest non-empty lane, unless a lower lane has waited too long.
+    pub(crate) fn pop(&mut self) -> Option<TMessage> {
+        let starved = (0..3).rev().find(|&i| self.passed_over[i] >= STARVATION_LIMIT && !self.lanes[i].is_empty());
+        let lane = starved.or_else(|| (0..3).find(|&i| !self.lanes[i].is_empty()))?;
+        for lower in lane + 1..3 {
+            if !self.lanes[lower].is_empty() {
+                self.passed_over[lower] += 1;
+            }
+        }
+        self.passed_over[lane] = 0;
+        return self.lanes[lane].pop_front();
+    }
+
+    /// Removes the first message matching `predicate`, looking at the higher lanes first.
+    pub(crate) fn take_first<P>(&mut self, mut predicate: P) -> Option<TMessage>
+    where
+        P : FnMut(&TMessage) -> bool
+    {
+        for lane in &mut self.lanes {
+            if let Some(i) = lane.iter().position(&mut predicate) {
+                return lane.remove(i);
+            }
+        }
+        return None;
+    }
+
+    pub(crate) fn take_all(&mut self) -> Vec<TMessage> {
+        return self.lanes.iter_mut().flat_map(|lane| lane.drain(..)).collect();
+    }
+}
+
+
+/// Like `start_mailbox`, but `MailboxContext::dequeue` serves `High` messages before `Normal`
+/// before `Low` ones, instead of strictly in order.
+///
+/// Messages of the same priority keep their order. After `STARVATION_LIMIT` (16) messages were
+/// served ahead of a waiting lower-priority message, that one is served next, so a busy high lane
+/// can't starve the others. With a bounded mailbox, the actor may take up to `n` more messages
+/// out of the mailbox to sort them.
+pub fn start_prioritized_mailbox<TMessage, F, Fut, S>(bounds: MailboxBounds<TMessage>, f: F, spawner: S) -> MailBox<TMessage>
+where
+    TMessage : Prioritized + Send + 'static,
+    F : FnOnce(MailboxContext<TMessage>) -> Fut,
+    Fut : Future + Send + 'static,
+    Fut::Output : ActorResult,
+    S : Spawner
+{
+    return start_mailbox_with(bounds, Some(TMessage::priority), f, spawner);
+}
+
+
+#[cfg(test)]
+use crate::{ReplyChannel, TestSpawner};
+
+#[cfg(test)]
+enum Msg {
+    Bulk(u32),
+    Urgent(u32),
+    Background(u32),
+    Get(ReplyChannel<Vec<(Priority, u32)>>),
+}
+
+#[cfg(test)]
+impl Prioritized for Msg {
+    fn priority(&self) -> Priority {
+        return match self {
+            Msg::Urgent(_) => Priority::High,
+            Msg::Bulk(_) => Priority::Normal,
+            // behind everything posted before it, no matter when the actor gets to it
+            Msg::Background(_) | Msg::Get(_) => Priority::Low,
+        };
+    }
+}
+
+#[test]
+fn test_priority() {
+    smol::block_on(async {
+        let (go_s, go_r) = async_channel::bounded::<()>(1);
+        let mb = start_prioritized_mailbox(MailboxBounds::Unbounded, move |ctx: MailboxContext<Msg>| async move {
+            let _ = go_r.recv().await;
+            let mut seen = Vec::new();
+            while let Some(msg) = ctx.dequeue().await {
+                match msg {
+                    Msg::Bulk(n) => seen.push((Priority::Normal, n)),
+                    Msg::Urgent(n) => seen.push((Priority::High, n)),
+                    Msg::Background(n) => seen.push((Priority::Low, n)),
+                    Msg::Get(rc) => { let _ = rc.reply(std::mem::take(&mut seen)); }
+                }
+            }
+        }, TestSpawner);
+
+        mb.post(Msg::Background(0)).unwrap();
+        mb.post(Msg::Bulk(0)).unwrap();
+        mb.post(Msg::Bulk(1)).unwrap();
+        mb.post(Msg::Urgent(0)).unwrap();
+        mb.post(Msg::Urgent(1)).unwrap();
+        go_s.send(()).await.unwrap();
+
+        let seen = mb.ask(Msg::Get).await.unwrap();
+        assert_eq!(seen, vec![(Priority::High, 0), (Priority::High, 1), (Priority::Normal, 0), (Priority::Normal, 1), (Priority::Low, 0)]);
+    });
+}
+
+#[test]
+fn test_starvation() {
+    let mut lanes = Lanes::new();
+    lanes.push(Priority::Low, 1000);
+    for i in 0..100 {
+        lanes.push(Priority::High, i);
+    }
+
+    let position = std::iter::from_fn(|| lanes.pop()).position(|n| n == 1000);
+    assert_eq!(position, Some(STARVATION_LIMIT as usize));
+    assert_eq!(lanes.len(), 100 - STARVATION_LIMIT as usize);
+}
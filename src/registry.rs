@@ -0,0 +1,145 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::{ActorId, Address, ExitCell, RegistryError, WeakAddress};
+
+
+struct Entry {
+    id: ActorId,
+    exit: Arc<ExitCell>,
+    // a `WeakAddress<TMessage>`
+    address: Box<dyn Any + Send + Sync>,
+}
+
+impl Entry {
+    // it has exited, but its name isn't removed yet
+    fn has_exited(&self) -> bool {
+        return self.exit.get().is_some();
+    }
+}
+
+/// Actors registered under a name, so they can be looked up instead of passing their `Address`es around.
+///
+/// Registering doesn't keep an actor alive. Its name is removed automatically once it has exited
+/// (supervised actors keep theirs across restarts). Clones share the same names.
+#[derive(Clone, Default)]
+pub struct Registry {
+    names: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        return Registry::default();
+    }
+
+    /// Fails with `RegistryError::AlreadyRegistered` if another actor is registered under `name`.
+    /// Registering the same actor again is fine, even under another name.
+    pub fn register<TMessage>(&self, name: impl Into<String>, address: &Address<TMessage>) -> Result<(), RegistryError>
+    where
+        TMessage : Send + 'static
+    {
+        let name = name.into();
+        let id = address.id();
+        {
+            let mut names = self.names.lock().unwrap();
+            match names.get(&name) {
+                Some(entry) if entry.id == id => return Ok(()),
+                Some(_) => return Err(RegistryError::AlreadyRegistered),
+                None => {}
+            }
+            names.insert(name.clone(), Entry { id, exit: address.exit.clone(), address: Box::new(address.downgrade()) });
+        }
+
+        let names = Arc::downgrade(&self.names);
+        address.exit.on_exit(move |_| {
+            let Some(names) = names.upgrade() else { return };
+            let mut names = names.lock().unwrap();
+            // the name may have been taken by another actor meanwhile
+            if names.get(&name).is_some_and(|entry| entry.id == id) {
+                names.remove(&name);
+            }
+        });
+        return Ok(());
+    }
+
+    /// Returns whether anything was registered under `name`.
+    pub fn unregister(&self, name: &str) -> bool {
+        return self.names.lock().unwrap().remove(name).is_some();
+    }
+
+    /// The actor registered under `name`, which has to have the message type `TMessage`.
+    pub fn lookup<TMessage>(&self, name: &str) -> Result<Address<TMessage>, RegistryError>
+    where
+        TMessage : 'static
+    {
+        let names = self.names.lock().unwrap();
+        let entry = names.get(name).filter(|entry| !entry.has_exited()).ok_or(RegistryError::NotFound)?;
+        let address = entry.address.downcast_ref::<WeakAddress<TMessage>>().ok_or(RegistryError::WrongType)?;
+        return address.upgrade().ok_or(RegistryError::NotFound);
+    }
+
+    /// The id of the actor registered under `name`, whatever its message type.
+    pub fn whereis(&self, name: &str) -> Option<ActorId> {
+        return self.names.lock().unwrap().get(name).filter(|entry| !entry.has_exited()).map(|entry| entry.id);
+    }
+}
+
+
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::{start_mailbox, MailboxBounds, MailboxContext, ReplyChannel, TestSpawner};
+
+#[cfg(test)]
+enum Msg {
+    Get(ReplyChannel<i32>),
+}
+
+#[cfg(test)]
+async fn answer(ctx: MailboxContext<Msg>) {
+    while let Some(Msg::Get(rc)) = ctx.dequeue().await {
+        let _ = rc.reply(42);
+    }
+}
+
+#[test]
+fn test_registry() {
+    smol::block_on(async {
+        let registry = Registry::new();
+        let mb = start_mailbox(MailboxBounds::Unbounded, answer, TestSpawner);
+        let other = start_mailbox(MailboxBounds::Unbounded, answer, TestSpawner);
+
+        registry.register("answer", &mb).unwrap();
+        registry.register("answer", &mb).unwrap();
+        assert_eq!(registry.register("answer", &other), Err(RegistryError::AlreadyRegistered));
+        assert_eq!(registry.whereis("answer"), Some(mb.id()));
+
+        let address = registry.lookup::<Msg>("answer").unwrap();
+        assert_eq!(address.ask(Msg::Get).await, Ok(42));
+        assert_eq!(registry.lookup::<i32>("answer").err(), Some(RegistryError::WrongType));
+        assert_eq!(registry.lookup::<Msg>("question").err(), Some(RegistryError::NotFound));
+
+        // the name is gone together with the actor, and free for another one
+        mb.stop().unwrap();
+        assert!(mb.shutdown().await.is_normal());
+        assert_eq!(registry.whereis("answer"), None);
+        assert_eq!(registry.lookup::<Msg>("answer").err(), Some(RegistryError::NotFound));
+        registry.register("answer", &other).unwrap();
+        assert_eq!(registry.whereis("answer"), Some(other.id()));
+
+        assert!(registry.unregister("answer"));
+        assert!(!registry.unregister("answer"));
+
+        // dropping the last `Address` still ends the actor
+        registry.register("other", &other).unwrap();
+        let (exited_s, exited_r) = async_channel::bounded(1);
+        other.exit.on_exit(move |_| {
+            let _ = exited_s.try_send(());
+        });
+        drop(other);
+        assert_eq!(crate::timeout(Duration::from_secs(5), exited_r.recv()).await, Some(Ok(())));
+        assert_eq!(registry.whereis("other"), None);
+    });
+}
@@ -0,0 +1,140 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use async_channel::{bounded, Receiver, Sender};
+use futures_util::Stream;
+
+use crate::{oneshot, Address, ExitCell, MailboxError};
+
+
+/// How many items the actor can send ahead of the consumer before `ReplyStream::send` waits.
+const REPLY_STREAM_BUFFER: usize = 16;
+
+/// Like a `ReplyChannel`, but for a reply consisting of many items, see `Address::ask_stream`.
+pub struct ReplyStream<T> {
+    s: Sender<T>,
+    // sent by `finish`, so the consumer can tell a complete stream from a truncated one
+    done: oneshot::Sender<()>,
+}
+
+impl<T> ReplyStream<T> {
+    /// Waits while the consumer is behind. Hands the item back if the consumer is gone,
+    /// in which case the actor should stop producing.
+    pub async fn send(&self, item: T) -> Result<(), T> {
+        return self.s.send(item).await.map_err(|e| e.into_inner());
+    }
+
+    /// Ends the stream. Dropping the `ReplyStream` without calling `finish` ends it as well,
+    /// but with an error, see `Address::ask_stream`.
+    pub fn finish(mut self) {
+        let _ = self.done.send(());
+        self.s.close();
+    }
+
+    /// True once the consumer has dropped the stream.
+    pub fn is_canceled(&self) -> bool {
+        return self.s.receiver_count() == 0;
+    }
+}
+
+
+/// The items sent through a `ReplyStream`, see `Address::ask_stream`.
+pub struct AskStream<T> {
+    r: Pin<Box<Receiver<T>>>,
+    // `None` once the stream has ended
+    done: Option<oneshot::Receiver<()>>,
+    exit: Arc<ExitCell>,
+}
+
+impl<T> Stream for AskStream<T> {
+    type Item = Result<T, MailboxError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(item) = std::task::ready!(self.r.as_mut().poll_next(cx)) {
+            return Poll::Ready(Some(Ok(item)));
+        }
+        let Some(done) = self.done.as_mut() else { return Poll::Ready(None) };
+        let result = std::task::ready!(Pin::new(done).poll(cx));
+        self.done = None;
+        return match result {
+            Ok(()) => Poll::Ready(None),
+            Err(e) => Poll::Ready(Some(Err(self.exit.ask_error(e)))),
+        };
+    }
+}
+
+impl<TMessage> Address<TMessage> {
+    /// Posts the message built by `cb` and returns the items the actor sends into the `ReplyStream`.
+    ///
+    /// The actor can be at most a few items ahead, after that `ReplyStream::send` waits for the consumer.
+    /// If the actor drops the `ReplyStream` without calling `finish`, the stream ends with
+    /// `Err(MailboxError::NoReply)`, or `Err(MailboxError::ActorPanicked)` if the actor panicked.
+    pub async fn ask_stream<T, F>(&self, cb: F) -> Result<AskStream<T>, MailboxError>
+    where
+        F : FnOnce(ReplyStream<T>) -> TMessage
+    {
+        let (s, r) = bounded(REPLY_STREAM_BUFFER);
+        let (done_s, done_r) = oneshot::channel();
+        self.post_async(cb(ReplyStream { s, done: done_s })).await?;
+        return Ok(AskStream { r: Box::pin(r), done: Some(done_r), exit: self.exit.clone() });
+    }
+}
+
+
+#[cfg(test)]
+use crate::{start_mailbox, MailboxBounds, MailboxContext, ReplyChannel, TestSpawner};
+
+#[cfg(test)]
+enum Msg {
+    Count(u32, ReplyStream<u32>),
+    Sent(ReplyChannel<u32>),
+    // sends `n` items, then panics
+    Crash(u32, ReplyStream<u32>),
+}
+
+#[test]
+fn test_ask_stream() {
+    use futures_util::{StreamExt, TryStreamExt};
+
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<Msg>| async move {
+            let mut sent = 0;
+            while let Some(msg) = ctx.dequeue().await {
+                match msg {
+                    Msg::Count(n, rs) => {
+                        for i in 0..n {
+                            if rs.send(i).await.is_err() {
+                                break;
+                            }
+                            sent += 1;
+                        }
+                        rs.finish();
+                    }
+                    Msg::Sent(rc) => { let _ = rc.reply(sent); }
+                    Msg::Crash(n, rs) => {
+                        for i in 0..n {
+                            let _ = rs.send(i).await;
+                        }
+                        panic!("crashed");
+                    }
+                }
+            }
+        }, TestSpawner);
+
+        let items: Result<Vec<u32>, _> = mb.ask_stream(|rs| Msg::Count(100, rs)).await.unwrap().try_collect().await;
+        assert_eq!(items, Ok((0..100).collect::<Vec<_>>()));
+
+        // the consumer only takes 5 items, the actor can't run more than the buffer ahead
+        let stream = mb.ask_stream(|rs| Msg::Count(1_000_000, rs)).await.unwrap();
+        let first: Vec<_> = stream.take(5).collect().await;
+        assert_eq!(first, vec![Ok(0), Ok(1), Ok(2), Ok(3), Ok(4)]);
+        let sent = mb.ask(Msg::Sent).await.unwrap();
+        assert!(sent <= 100 + 5 + REPLY_STREAM_BUFFER as u32 + 1, "sent {}", sent);
+
+        // a truncated stream ends with an error
+        let items: Vec<_> = mb.ask_stream(|rs| Msg::Crash(3, rs)).await.unwrap().collect().await;
+        assert_eq!(items, vec![Ok(0), Ok(1), Ok(2), Err(MailboxError::ActorPanicked)]);
+    });
+}
@@ -0,0 +1,98 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_util::Sink;
+
+use crate::{Address, MailboxError, TryPostError};
+
+
+type Sending = Pin<Box<dyn Future<Output = Result<(), MailboxError>> + Send>>;
+
+/// Feeds messages into a mailbox, e.g. with `stream.map(Ok).forward(mb.sink())`.
+///
+/// While a bounded mailbox is full, the sink isn't ready, so the producer waits like with `post_async`.
+/// Closing the sink only flushes it, it doesn't close the mailbox.
+pub struct MailboxSink<TMessage> {
+    address: Address<TMessage>,
+    // the message that didn't fit into the mailbox on `start_send`
+    sending: Option<Sending>,
+}
+
+impl<TMessage> Address<TMessage> {
+    /// Keeps the actor alive as long as the sink exists, like an `Address`.
+    pub fn sink(&self) -> MailboxSink<TMessage> {
+        return MailboxSink { address: self.clone(), sending: None };
+    }
+}
+
+impl<TMessage: Send + 'static> Sink<TMessage> for MailboxSink<TMessage> {
+    type Error = MailboxError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), MailboxError>> {
+        return self.poll_flush(cx);
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, msg: TMessage) -> Result<(), MailboxError> {
+        return match self.address.try_post(msg) {
+            Ok(()) => Ok(()),
+            Err(TryPostError::Closed(msg)) => {
+                crate::discard(msg);
+                Err(MailboxError::Closed)
+            }
+            Err(TryPostError::Full(msg)) => {
+                let address = self.address.clone();
+                self.sending = Some(Box::pin(async move { address.post_async(msg).await }));
+                Ok(())
+            }
+        };
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), MailboxError>> {
+        let Some(sending) = self.sending.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+        let result = ready!(sending.as_mut().poll(cx));
+        self.sending = None;
+        return Poll::Ready(result);
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), MailboxError>> {
+        return self.poll_flush(cx);
+    }
+}
+
+
+#[cfg(test)]
+use crate::{start_mailbox, MailboxBounds, MailboxContext, ReplyChannel, TestSpawner};
+
+#[cfg(test)]
+enum Msg {
+    Add(i32),
+    Get(ReplyChannel<i32>),
+}
+
+#[test]
+fn test_sink() {
+    use futures_util::{stream, SinkExt, StreamExt};
+
+    smol::block_on(async {
+        // bounded, so the sink has to wait for the actor every other message
+        let mb = start_mailbox(MailboxBounds::Bounded(1), |ctx: MailboxContext<Msg>| async move {
+            let mut sum = 0;
+            while let Some(msg) = ctx.dequeue().await {
+                match msg {
+                    Msg::Add(n) => sum += n,
+                    Msg::Get(rc) => { let _ = rc.reply(sum); }
+                }
+            }
+        }, TestSpawner);
+
+        stream::iter(1..=100).map(|n| Ok(Msg::Add(n))).forward(mb.sink()).await.unwrap();
+        assert_eq!(mb.ask(Msg::Get).await, Ok(5050));
+
+        let mut sink = mb.sink();
+        mb.close();
+        assert_eq!(sink.send(Msg::Add(1)).await, Err(MailboxError::Closed));
+    });
+}
@@ -0,0 +1,109 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+
+pub type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Runs actor futures to completion on some executor.
+///
+/// The future is fire-and-forget, `start_mailbox` tracks completion itself,
+/// so implementations only need to get it polled.
+pub trait Spawner {
+    fn spawn(&self, fut: BoxFuture);
+}
+
+impl<S: Spawner + ?Sized> Spawner for &S {
+    fn spawn(&self, fut: BoxFuture) {
+        (**self).spawn(fut);
+    }
+}
+
+impl<S: Spawner + ?Sized> Spawner for Arc<S> {
+    fn spawn(&self, fut: BoxFuture) {
+        (**self).spawn(fut);
+    }
+}
+
+
+/// Spawns onto the global async-std executor.
+#[cfg(feature = "async-std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsyncStdSpawner;
+
+#[cfg(feature = "async-std")]
+impl Spawner for AsyncStdSpawner {
+    fn spawn(&self, fut: BoxFuture) {
+        async_std::task::spawn(fut);
+    }
+}
+
+
+/// Spawns onto the tokio runtime of the calling context, see `tokio::spawn`.
+///
+/// To spawn onto a specific runtime, pass its `tokio::runtime::Handle` instead.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioSpawner;
+
+#[cfg(feature = "tokio")]
+impl Spawner for TokioSpawner {
+    fn spawn(&self, fut: BoxFuture) {
+        tokio::spawn(fut);
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Spawner for tokio::runtime::Handle {
+    fn spawn(&self, fut: BoxFuture) {
+        tokio::runtime::Handle::spawn(self, fut);
+    }
+}
+
+
+/// Spawns onto the global smol executor.
+///
+/// To spawn onto a specific executor, pass an `Arc<smol::Executor>` instead.
+#[cfg(feature = "smol")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SmolSpawner;
+
+#[cfg(feature = "smol")]
+impl Spawner for SmolSpawner {
+    fn spawn(&self, fut: BoxFuture) {
+        smol::spawn(fut).detach();
+    }
+}
+
+#[cfg(feature = "smol")]
+impl Spawner for smol::Executor<'static> {
+    fn spawn(&self, fut: BoxFuture) {
+        smol::Executor::spawn(self, fut).detach();
+    }
+}
+
+
+#[cfg(feature = "futures-executor")]
+impl Spawner for futures_executor::ThreadPool {
+    fn spawn(&self, fut: BoxFuture) {
+        self.spawn_ok(fut);
+    }
+}
+
+
+#[cfg(all(test, feature = "tokio"))]
+#[test]
+fn test_tokio_handle() {
+    use crate::{start_mailbox, MailboxBounds, MailboxContext, ReplyChannel};
+
+    let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    let mb = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<ReplyChannel<i32>>| async move {
+        while let Some(rc) = ctx.dequeue().await {
+            let _ = rc.reply(42);
+        }
+    }, rt.handle().clone());
+
+    let val = rt.block_on(mb.ask(|rc| rc));
+    assert_eq!(val, Ok(42));
+}
@@ -0,0 +1,388 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_channel::{unbounded, Receiver, Sender};
+use futures_util::future::{abortable, AbortHandle};
+use futures_util::FutureExt;
+
+use crate::{spawn_tracked, ActorExit, ActorResult, Address, ExitCell, Inbox, JoinHandle, MailboxBounds, MailboxContext, Spawner};
+
+
+/// Which children are restarted when one of them fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartStrategy {
+    /// Only the failed child.
+    OneForOne,
+    /// All children.
+    OneForAll,
+    /// The failed child and every child added after it.
+    RestForOne,
+}
+
+/// Delay before a restart, doubling with every restart inside the intensity window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Backoff {
+    pub fn none() -> Self {
+        return Backoff { initial: Duration::ZERO, max: Duration::ZERO };
+    }
+
+    pub fn exponential(initial: Duration, max: Duration) -> Self {
+        return Backoff { initial, max };
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+        return self.initial.saturating_mul(factor).min(self.max);
+    }
+}
+
+
+type ChildFuture = Pin<Box<dyn Future<Output = ActorExit> + Send>>;
+
+struct Child {
+    start: Box<dyn FnMut() -> ChildFuture + Send>,
+    // closes and drains the mailbox once the child is gone for good
+    close: Box<dyn Fn() + Send>,
+    exit: Arc<ExitCell>,
+    generation: u64,
+    abort: Option<AbortHandle>,
+    finished: bool,
+}
+
+/// (child index, generation, exit), `None` if the child was stopped by the supervisor.
+type Report = (usize, u64, Option<ActorExit>);
+
+
+/// Runs a group of actors and restarts them when they panic or return an error.
+///
+/// Each child keeps its mailbox across restarts, so the `Address`es returned by `add_child`
+/// stay valid; messages that were queued but not yet dequeued are handled by the new incarnation.
+/// A child that exits normally (e.g. because its mailbox was closed) isn't restarted.
+pub struct Supervisor {
+    strategy: RestartStrategy,
+    max_restarts: usize,
+    within: Duration,
+    backoff: Backoff,
+    children: Vec<Child>,
+}
+
+impl Supervisor {
+    /// Defaults to at most 3 restarts within 5 seconds, without backoff.
+    pub fn new(strategy: RestartStrategy) -> Self {
+        return Supervisor {
+            strategy,
+            max_restarts: 3,
+            within: Duration::from_secs(5),
+            backoff: Backoff::none(),
+            children: Vec::new(),
+        };
+    }
+
+    /// If there are more than `max_restarts` restarts within `within`, the supervisor
+    /// stops all children and exits.
+    pub fn max_restarts(mut self, max_restarts: usize, within: Duration) -> Self {
+        self.max_restarts = max_restarts;
+        self.within = within;
+        return self;
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        return self;
+    }
+
+    /// `f` is called again with a fresh `MailboxContext` (on the same mailbox) for every restart.
+    pub fn add_child<TMessage, F, Fut>(&mut self, bounds: MailboxBounds<TMessage>, mut f: F) -> Address<TMessage>
+    where
+        TMessage : Send + 'static,
+        F : FnMut(MailboxContext<TMessage>) -> Fut + Send + 'static,
+        Fut : Future + Send + 'static,
+        Fut::Output : ActorResult
+    {
+        let (s,r,overflow) = bounds.channel();
+        let (control_s, control_r) = unbounded();
+        let exit = Arc::new(ExitCell::default());
+
+        let address = Address::new(s, control_s, exit.clone(), overflow);
+
+        let inbox = Inbox::new(r, control_r, address.downgrade(), None);
+
+        let inbox_ = inbox.clone();
+        let exit_ = exit.clone();
+        let start = move || -> ChildFuture {
+            // a killed child counts as failed and is restarted
+            return Box::pin(exit_.killable(f(MailboxContext::new(inbox_.clone()))));
+        };
+        let close = move || inbox.close_and_drain();
+
+        self.children.push(Child {
+            start: Box::new(start),
+            close: Box::new(close),
+            exit,
+            generation: 0,
+            abort: None,
+            finished: false,
+        });
+
+        return address;
+    }
+
+    /// Starts all children in the order they were added.
+    ///
+    /// The returned handle resolves once every child has exited normally,
+    /// or with an `ActorExit::Error` if the restart intensity was exceeded.
+    pub fn start<S>(self, spawner: S) -> JoinHandle
+    where
+        S : Spawner + Send + Sync + 'static
+    {
+        let spawner = Arc::new(spawner);
+        let (reports_s, reports_r) = unbounded();
+
+        let running = Running {
+            spec: self,
+            spawner: spawner.clone(),
+            reports_s,
+            reports_r,
+            pending: VecDeque::new(),
+        };
+
+        return spawn_tracked(&spawner, Arc::new(ExitCell::default()), running.run(), || {});
+    }
+}
+
+
+struct Running<S> {
+    spec: Supervisor,
+    spawner: Arc<S>,
+    reports_s: Sender<Report>,
+    reports_r: Receiver<Report>,
+    // reports which arrived while waiting for a specific child to stop
+    pending: VecDeque<Report>,
+}
+
+impl<S: Spawner> Running<S> {
+    async fn run(mut self) -> Result<(), &'static str> {
+        for i in 0..self.spec.children.len() {
+            self.start_child(i);
+        }
+
+        let mut restarts = VecDeque::<Instant>::new();
+
+        while self.spec.children.iter().any(|c| !c.finished) {
+            let (i, generation, exit) = self.next_report().await;
+            let child = &mut self.spec.children[i];
+            if generation != child.generation {
+                continue;
+            }
+            child.abort = None;
+
+            let exit = match exit {
+                None => continue,
+                Some(ActorExit::Normal) => {
+                    child.finished = true;
+                    child.exit.set(ActorExit::Normal);
+                    (child.close)();
+                    continue;
+                }
+                Some(exit) => exit,
+            };
+
+            let now = Instant::now();
+            restarts.push_back(now);
+            while restarts.front().is_some_and(|t| now.duration_since(*t) > self.spec.within) {
+                restarts.pop_front();
+            }
+            if restarts.len() > self.spec.max_restarts {
+                self.spec.children[i].exit.set(exit);
+                for j in 0..self.spec.children.len() {
+                    self.stop_child(j).await;
+                    let child = &self.spec.children[j];
+                    // so that monitors and links of the stopped children fire as well
+                    if child.exit.get().is_none() {
+                        child.exit.set(ActorExit::Killed);
+                    }
+                    (child.close)();
+                }
+                return Err("restart intensity exceeded");
+            }
+
+            let delay = self.spec.backoff.delay(restarts.len() as u32 - 1);
+            if !delay.is_zero() {
+                futures_timer::Delay::new(delay).await;
+            }
+
+            let affected = match self.spec.strategy {
+                RestartStrategy::OneForOne => i..i + 1,
+                RestartStrategy::OneForAll => 0..self.spec.children.len(),
+                RestartStrategy::RestForOne => i..self.spec.children.len(),
+            };
+            for j in affected.clone() {
+                self.stop_child(j).await;
+            }
+            for j in affected {
+                if j == i || !self.spec.children[j].finished {
+                    self.start_child(j);
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn start_child(&mut self, i: usize) {
+        let child = &mut self.spec.children[i];
+        child.generation += 1;
+        child.finished = false;
+
+        let (fut, abort) = abortable(AssertUnwindSafe((child.start)()).catch_unwind());
+        child.abort = Some(abort);
+
+        let generation = child.generation;
+        let reports = self.reports_s.clone();
+        self.spawner.spawn(Box::pin(async move {
+            let mut fut = Box::pin(fut);
+            let exit = match fut.as_mut().await {
+                Ok(Ok(exit)) => Some(exit),
+                Ok(Err(payload)) => Some(ActorExit::from_panic(payload)),
+                Err(_aborted) => {
+                    // like a killed actor, see `ExitCell::killable`
+                    crate::discard(fut);
+                    None
+                }
+            };
+            let _ = reports.send((i, generation, exit)).await;
+        }));
+    }
+
+    /// Aborts the child (if it's running) and waits until it's gone,
+    /// so that two incarnations never read from the same mailbox at the same time.
+    async fn stop_child(&mut self, i: usize) {
+        let Some(abort) = self.spec.children[i].abort.take() else { return };
+        abort.abort();
+
+        let generation = self.spec.children[i].generation;
+        // it may have exited on its own already, and its report been set aside while stopping another child
+        if let Some(pos) = self.pending.iter().position(|report| report.0 == i && report.1 == generation) {
+            self.pending.remove(pos);
+            return;
+        }
+        loop {
+            let report = self.reports_r.recv().await.expect("the supervisor holds a sender");
+            if report.0 == i && report.1 == generation {
+                return;
+            }
+            self.pending.push_back(report);
+        }
+    }
+
+    async fn next_report(&mut self) -> Report {
+        if let Some(report) = self.pending.pop_front() {
+            return report;
+        }
+        return self.reports_r.recv().await.expect("the supervisor holds a sender");
+    }
+}
+
+
+#[cfg(test)]
+use crate::{ReplyChannel, TestSpawner};
+
+#[cfg(test)]
+enum Msg {
+    Increment,
+    Fail,
+    Panic,
+    Get(ReplyChannel<i32>),
+}
+
+#[cfg(test)]
+async fn child_fn(ctx: MailboxContext<Msg>) -> Result<(), String> {
+    let mut count = 0;
+    while let Some(msg) = ctx.dequeue().await {
+        match msg {
+            Msg::Increment => count += 1,
+            Msg::Fail => return Err("failed".to_string()),
+            Msg::Panic => panic!("panicked"),
+            Msg::Get(rc) => { let _ = rc.reply(count); }
+        }
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+async fn restart_one(strategy: RestartStrategy, failing: usize) -> Vec<i32> {
+    let mut sup = Supervisor::new(strategy);
+    let children: Vec<_> = (0..3).map(|_| sup.add_child(MailboxBounds::Unbounded, child_fn)).collect();
+    let _handle = sup.start(TestSpawner);
+
+    for c in &children {
+        c.post(Msg::Increment).unwrap();
+    }
+    children[failing].post(Msg::Fail).unwrap();
+
+    // answered by the new incarnation, which is only started once the others were restarted
+    assert_eq!(children[failing].ask(Msg::Get).await, Ok(0));
+
+    let mut counts = Vec::new();
+    for c in &children {
+        counts.push(c.ask(Msg::Get).await.unwrap());
+    }
+    return counts;
+}
+
+#[test]
+fn test_strategies() {
+    smol::block_on(async {
+        assert_eq!(restart_one(RestartStrategy::OneForOne, 1).await, vec![1, 0, 1]);
+        assert_eq!(restart_one(RestartStrategy::OneForAll, 1).await, vec![0, 0, 0]);
+        assert_eq!(restart_one(RestartStrategy::RestForOne, 1).await, vec![1, 0, 0]);
+    });
+}
+
+#[test]
+fn test_failing_together() {
+    smol::block_on(async {
+        let mut sup = Supervisor::new(RestartStrategy::OneForAll)
+            .backoff(Backoff::exponential(Duration::from_millis(50), Duration::from_millis(50)));
+        let children: Vec<_> = (0..3).map(|_| sup.add_child(MailboxBounds::Unbounded, child_fn)).collect();
+        let _handle = sup.start(TestSpawner);
+
+        // 2 fails while the supervisor waits to restart everything after 0 failed
+        children[0].post(Msg::Fail).unwrap();
+        futures_timer::Delay::new(Duration::from_millis(10)).await;
+        children[2].post(Msg::Fail).unwrap();
+
+        for c in &children {
+            assert_eq!(crate::timeout(Duration::from_secs(5), c.ask(Msg::Get)).await, Some(Ok(0)));
+        }
+    });
+}
+
+#[test]
+fn test_intensity() {
+    smol::block_on(async {
+        let mut sup = Supervisor::new(RestartStrategy::OneForOne)
+            .max_restarts(1, Duration::from_secs(60))
+            .backoff(Backoff::exponential(Duration::from_millis(1), Duration::from_millis(10)));
+        let child = sup.add_child(MailboxBounds::Unbounded, child_fn);
+        let handle = sup.start(TestSpawner);
+
+        child.post(Msg::Panic).unwrap();
+        assert_eq!(child.ask(Msg::Get).await, Ok(0));
+
+        // second restart within the window, the supervisor gives up
+        child.post(Msg::Fail).unwrap();
+        assert!(matches!(handle.await, ActorExit::Error(_)));
+        assert!(matches!(child.exit(), Some(ActorExit::Error(_))));
+        assert_eq!(child.post(Msg::Increment), Err(crate::MailboxError::Closed));
+    });
+}
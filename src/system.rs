@@ -0,0 +1,198 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use async_channel::bounded;
+
+use crate::{
+    start_actor, start_mailbox, start_prioritized_mailbox, Actor, ActorExit, ActorId, ActorResult, ExitCell, MailBox,
+    MailboxBounds, MailboxContext, Prioritized, Spawner
+};
+
+
+struct Tracked {
+    id: ActorId,
+    exit: Arc<ExitCell>,
+    // closes the mailbox, without keeping the actor alive until then
+    close: Box<dyn Fn() + Send>,
+}
+
+/// Returned by `ActorSystem::shutdown`.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownReport {
+    /// The actors which stopped in time and how they exited, in the order they were stopped.
+    pub stopped: Vec<(ActorId, ActorExit)>,
+    /// The actors which were still running at the deadline, they were killed.
+    pub timed_out: Vec<ActorId>,
+}
+
+impl ShutdownReport {
+    pub fn all_stopped(&self) -> bool {
+        return self.timed_out.is_empty();
+    }
+}
+
+
+/// Starts actors on one spawner and keeps track of them until they exit, so they can be shut down together.
+///
+/// Clones share the same spawner and actors, e.g. for actors starting other actors.
+pub struct ActorSystem<S> {
+    spawner: Arc<S>,
+    // in the order they were started
+    actors: Arc<Mutex<Vec<Tracked>>>,
+}
+
+impl<S> Clone for ActorSystem<S> {
+    fn clone(&self) -> Self {
+        return ActorSystem { spawner: self.spawner.clone(), actors: self.actors.clone() };
+    }
+}
+
+impl<S: Spawner> ActorSystem<S> {
+    pub fn new(spawner: S) -> Self {
+        return ActorSystem { spawner: Arc::new(spawner), actors: Arc::new(Mutex::new(Vec::new())) };
+    }
+
+    /// `start_mailbox` on the system's spawner.
+    pub fn start_mailbox<TMessage, F, Fut>(&self, bounds: MailboxBounds<TMessage>, f: F) -> MailBox<TMessage>
+    where
+        TMessage : Send + 'static,
+        F : FnOnce(MailboxContext<TMessage>) -> Fut,
+        Fut : Future + Send + 'static,
+        Fut::Output : ActorResult
+    {
+        return self.track(start_mailbox(bounds, f, &*self.spawner));
+    }
+
+    /// `start_prioritized_mailbox` on the system's spawner.
+    pub fn start_prioritized_mailbox<TMessage, F, Fut>(&self, bounds: MailboxBounds<TMessage>, f: F) -> MailBox<TMessage>
+    where
+        TMessage : Prioritized + Send + 'static,
+        F : FnOnce(MailboxContext<TMessage>) -> Fut,
+        Fut : Future + Send + 'static,
+        Fut::Output : ActorResult
+    {
+        return self.track(start_prioritized_mailbox(bounds, f, &*self.spawner));
+    }
+
+    /// `start_actor` on the system's spawner.
+    pub fn start_actor<A: Actor>(&self, actor: A, bounds: MailboxBounds<A::Msg>) -> MailBox<A::Msg> {
+        return self.track(start_actor(actor, bounds, &*self.spawner));
+    }
+
+    /// The actors which are still running, in the order they were started.
+    pub fn actors(&self) -> Vec<ActorId> {
+        return self.actors.lock().unwrap().iter().map(|actor| actor.id).collect();
+    }
+
+    /// Stops all actors in the reverse order they were started, so an actor is stopped before
+    /// the actors it was started after (and may depend on) are.
+    ///
+    /// Each actor's mailbox is closed and the actor gets to handle what's still queued, like
+    /// `MailBox::shutdown`. Actors still running at `deadline` are killed and reported as timed out;
+    /// once it has passed, the remaining actors only stop in time if they do right away.
+    pub async fn shutdown(&self, deadline: Instant) -> ShutdownReport {
+        let actors = std::mem::take(&mut *self.actors.lock().unwrap());
+        let mut report = ShutdownReport::default();
+        for actor in actors.into_iter().rev() {
+            (actor.close)();
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match crate::timeout(timeout, exited(&actor.exit)).await {
+                Some(exit) => report.stopped.push((actor.id, exit)),
+                None => {
+                    actor.exit.kill();
+                    report.timed_out.push(actor.id);
+                }
+            }
+        }
+        return report;
+    }
+
+    fn track<TMessage>(&self, mb: MailBox<TMessage>) -> MailBox<TMessage>
+    where
+        TMessage : Send + 'static
+    {
+        let id = mb.id();
+        let address = mb.downgrade();
+        self.actors.lock().unwrap().push(Tracked {
+            id,
+            exit: mb.exit.clone(),
+            close: Box::new(move || {
+                // otherwise every `Address` is gone and the mailbox is closed already
+                if let Some(address) = address.upgrade() {
+                    address.close();
+                }
+            }),
+        });
+
+        let actors = Arc::downgrade(&self.actors);
+        mb.exit.on_exit(move |_| {
+            if let Some(actors) = actors.upgrade() {
+                actors.lock().unwrap().retain(|actor| actor.id != id);
+            }
+        });
+        return mb;
+    }
+}
+
+async fn exited(exit: &ExitCell) -> ActorExit {
+    let (s, r) = bounded(1);
+    exit.on_exit(move |exit| {
+        let _ = s.try_send(exit.clone());
+    });
+    return r.recv().await.expect("watchers are called once the exit is set");
+}
+
+
+#[cfg(test)]
+use std::time::Duration;
+
+#[cfg(test)]
+use crate::TestSpawner;
+
+#[test]
+fn test_shutdown() {
+    smol::block_on(async {
+        let system = ActorSystem::new(TestSpawner);
+        let stopped = Arc::new(Mutex::new(Vec::new()));
+
+        // started first, so it's stopped last and doesn't hold up the others
+        let stuck = system.start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<i32>| async move {
+            while ctx.dequeue().await.is_some() {
+                std::future::pending::<()>().await;
+            }
+        });
+
+        let mut mbs = Vec::new();
+        for name in ["db", "cache", "api"] {
+            let stopped = stopped.clone();
+            mbs.push(system.start_mailbox(MailboxBounds::Unbounded, move |ctx: MailboxContext<i32>| async move {
+                let mut sum = 0;
+                while let Some(n) = ctx.dequeue().await {
+                    sum += n;
+                }
+                stopped.lock().unwrap().push((name, sum));
+            }));
+        }
+        let finished = system.start_mailbox(MailboxBounds::Unbounded, |_ctx: MailboxContext<i32>| async move {});
+        assert!(finished.shutdown().await.is_normal());
+        assert_eq!(system.actors(), vec![stuck.id(), mbs[0].id(), mbs[1].id(), mbs[2].id()]);
+
+        // queued messages are still handled
+        for mb in &mbs {
+            mb.post(1).unwrap();
+            mb.post(2).unwrap();
+        }
+        stuck.post(1).unwrap();
+
+        let report = system.shutdown(Instant::now() + Duration::from_millis(50)).await;
+        assert_eq!(report.timed_out, vec![stuck.id()]);
+        assert!(!report.all_stopped());
+        let ids: Vec<_> = report.stopped.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![mbs[2].id(), mbs[1].id(), mbs[0].id()]);
+        assert!(report.stopped.iter().all(|(_, exit)| exit.is_normal()));
+        assert_eq!(*stopped.lock().unwrap(), vec![("api", 3), ("cache", 3), ("db", 3)]);
+        assert!(matches!(stuck.handle.await, ActorExit::Killed));
+        assert!(system.actors().is_empty());
+    });
+}
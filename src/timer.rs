@@ -0,0 +1,295 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::{TryPostError, WeakAddress};
+
+
+/// Cancels a timer started by `post_after`, `post_interval` or `MailboxContext::schedule_self`.
+///
+/// Dropping the handle does *not* cancel the timer, so `post_after` can be fire-and-forget.
+/// Timers stop by themselves once the actor's mailbox is closed or every `Address` is gone.
+#[derive(Clone)]
+pub struct TimerHandle {
+    timer: Arc<Timer>,
+}
+
+impl TimerHandle {
+    /// The message isn't posted if the timer hasn't fired yet, an interval doesn't fire again.
+    /// The message (or the closure of an interval) is dropped right away.
+    pub fn cancel(&self) {
+        self.timer.canceled.store(true, AtomicOrdering::SeqCst);
+        crate::discard(self.timer.task.lock().unwrap().take());
+    }
+
+    pub fn is_canceled(&self) -> bool {
+        return self.timer.canceled.load(AtomicOrdering::SeqCst);
+    }
+}
+
+impl fmt::Debug for TimerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimerHandle").field("canceled", &self.is_canceled()).finish()
+    }
+}
+
+
+/// Called when the timer fires, returns `false` once the timer is done.
+type Task = Box<dyn FnMut() -> bool + Send>;
+
+/// Shared between the `TimerHandle`s and the queued `Entry`.
+struct Timer {
+    canceled: AtomicBool,
+    // `None` once canceled, or while the timer thread is running it
+    task: Mutex<Option<Task>>,
+}
+
+struct Entry {
+    at: Instant,
+    // keeps timers with the same deadline in the order they were scheduled
+    seq: u64,
+    period: Option<Duration>,
+    timer: Arc<Timer>,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        return (self.at, self.seq) == (other.at, other.seq);
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        return (self.at, self.seq).cmp(&(other.at, other.seq));
+    }
+}
+
+
+/// A single background thread firing all timers, so they work the same on every executor.
+///
+/// Firing only ever `try_post`s, so a full mailbox can't stall the thread.
+struct Timers {
+    queue: Mutex<BinaryHeap<Reverse<Entry>>>,
+    wake: Condvar,
+    seq: AtomicU64,
+}
+
+impl Timers {
+    fn get() -> &'static Timers {
+        static TIMERS: OnceLock<&'static Timers> = OnceLock::new();
+        return TIMERS.get_or_init(|| {
+            let timers: &'static Timers = Box::leak(Box::new(Timers {
+                queue: Mutex::new(BinaryHeap::new()),
+                wake: Condvar::new(),
+                seq: AtomicU64::new(0),
+            }));
+            std::thread::Builder::new()
+                .name("mailboxxy-timer".into())
+                .spawn(move || timers.run())
+                .expect("failed to spawn the timer thread");
+            timers
+        });
+    }
+
+    fn push(&self, mut entry: Entry) {
+        entry.seq = self.seq.fetch_add(1, AtomicOrdering::Relaxed);
+        self.queue.lock().unwrap().push(Reverse(entry));
+        self.wake.notify_one();
+    }
+
+    fn run(&self) {
+        loop {
+            let mut entry = self.next();
+            // not holding the lock while it runs, an interval may cancel itself
+            let Some(mut task) = entry.timer.task.lock().unwrap().take() else { continue };
+            // a panicking interval callback only stops its own timer
+            let again = std::panic::catch_unwind(AssertUnwindSafe(&mut task)).unwrap_or(false);
+            let Some(period) = entry.period.filter(|_| again) else {
+                crate::discard(task);
+                continue;
+            };
+            *entry.timer.task.lock().unwrap() = Some(task);
+            // canceled while it was running, `cancel` found nothing to drop
+            if entry.timer.canceled.load(AtomicOrdering::SeqCst) {
+                crate::discard(entry.timer.task.lock().unwrap().take());
+                continue;
+            }
+            // don't try to catch up on ticks missed while the thread was busy
+            entry.at = (entry.at + period).max(Instant::now());
+            self.push(entry);
+        }
+    }
+
+    /// Blocks until the earliest timer is due.
+    fn next(&self) -> Entry {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            match queue.peek() {
+                None => queue = self.wake.wait(queue).unwrap(),
+                Some(Reverse(entry)) if entry.at <= now => return queue.pop().unwrap().0,
+                Some(Reverse(entry)) => {
+                    let wait = entry.at - now;
+                    queue = self.wake.wait_timeout(queue, wait).unwrap().0;
+                }
+            }
+        }
+    }
+}
+
+
+fn schedule(at: Instant, period: Option<Duration>, task: Task) -> TimerHandle {
+    let timer = Arc::new(Timer { canceled: AtomicBool::new(false), task: Mutex::new(Some(task)) });
+    Timers::get().push(Entry { at, seq: 0, period, timer: timer.clone() });
+    return TimerHandle { timer };
+}
+
+/// Posts `msg` to `address` after `delay`, dropping it if the mailbox is full or gone by then.
+pub(crate) fn post_after<TMessage>(address: WeakAddress<TMessage>, delay: Duration, msg: TMessage) -> TimerHandle
+where
+    TMessage : Send + 'static
+{
+    let mut msg = Some(msg);
+    return schedule(Instant::now() + delay, None, Box::new(move || {
+        if let (Some(address), Some(msg)) = (address.upgrade(), msg.take()) {
+            crate::discard(address.try_post(msg));
+        }
+        return false;
+    }));
+}
+
+/// Posts `f()` to `address` every `period`, skipping ticks while the mailbox is full.
+pub(crate) fn post_interval<TMessage, F>(address: WeakAddress<TMessage>, period: Duration, mut f: F) -> TimerHandle
+where
+    TMessage : Send + 'static,
+    F : FnMut() -> TMessage + Send + 'static
+{
+    assert!(!period.is_zero(), "post_interval: period must be non-zero");
+    return schedule(Instant::now() + period, Some(period), Box::new(move || {
+        let Some(address) = address.upgrade() else { return false };
+        let result = address.try_post(f());
+        let again = !matches!(result, Err(TryPostError::Closed(_)));
+        crate::discard(result);
+        return again;
+    }));
+}
+
+
+#[cfg(test)]
+use crate::{start_mailbox, MailBox, MailboxBounds, MailboxContext, ReplyChannel, TestSpawner};
+
+#[cfg(test)]
+enum Msg {
+    Tick,
+    Reminder(i32),
+    Get(ReplyChannel<Vec<i32>>),
+}
+
+#[cfg(test)]
+async fn recorder(ctx: MailboxContext<Msg>) {
+    let mut seen = Vec::new();
+    while let Some(msg) = ctx.dequeue().await {
+        match msg {
+            Msg::Tick => seen.push(0),
+            Msg::Reminder(n) => seen.push(n),
+            Msg::Get(rc) => { let _ = rc.reply(seen.clone()); }
+        }
+    }
+}
+
+#[cfg(test)]
+async fn sleep(ms: u64) {
+    futures_timer::Delay::new(Duration::from_millis(ms)).await;
+}
+
+#[test]
+fn test_post_after() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, recorder, TestSpawner);
+
+        mb.post_after(Duration::from_millis(20), Msg::Reminder(2));
+        mb.post_after(Duration::from_millis(10), Msg::Reminder(1));
+        let canceled = mb.post_after(Duration::from_millis(10), Msg::Reminder(3));
+        canceled.cancel();
+        assert!(canceled.is_canceled());
+        assert_eq!(mb.ask(Msg::Get).await, Ok(vec![]));
+
+        sleep(100).await;
+        assert_eq!(mb.ask(Msg::Get).await, Ok(vec![1, 2]));
+    });
+}
+
+#[test]
+fn test_post_interval() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, recorder, TestSpawner);
+
+        let timer = mb.post_interval(Duration::from_millis(5), || Msg::Tick);
+        sleep(100).await;
+        timer.cancel();
+        sleep(20).await;
+
+        let ticks = mb.ask(Msg::Get).await.unwrap().len();
+        assert!(ticks >= 2, "only {} ticks", ticks);
+        sleep(50).await;
+        assert_eq!(mb.ask(Msg::Get).await.unwrap().len(), ticks);
+    });
+}
+
+#[test]
+fn test_cancel_drops_task() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, recorder, TestSpawner);
+
+        // not due for an hour, but gone as soon as it's canceled
+        let token = Arc::new(());
+        let held = token.clone();
+        let timer = mb.post_interval(Duration::from_secs(3600), move || {
+            let _ = &held;
+            return Msg::Tick;
+        });
+        assert_eq!(Arc::strong_count(&token), 2);
+        timer.cancel();
+        assert_eq!(Arc::strong_count(&token), 1);
+    });
+}
+
+#[test]
+fn test_schedule_self() {
+    smol::block_on(async {
+        let mb = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<Msg>| async move {
+            ctx.schedule_self(Duration::from_millis(10), Msg::Reminder(1));
+            recorder(ctx).await;
+        }, TestSpawner);
+
+        sleep(100).await;
+        assert_eq!(mb.ask(Msg::Get).await, Ok(vec![1]));
+    });
+}
+
+#[test]
+fn test_timer_doesnt_keep_actor_alive() {
+    smol::block_on(async {
+        let MailBox { address, handle } = start_mailbox(MailboxBounds::Unbounded, |ctx: MailboxContext<Msg>| async move {
+            ctx.schedule_self_interval(Duration::from_millis(5), || Msg::Tick);
+            recorder(ctx).await;
+        }, TestSpawner);
+
+        address.post_interval(Duration::from_millis(5), || Msg::Tick);
+        sleep(20).await;
+        drop(address);
+        assert!(crate::timeout(Duration::from_secs(5), handle).await.is_some());
+    });
+}